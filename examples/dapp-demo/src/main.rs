@@ -3,12 +3,11 @@ use {
     monedero_mesh::{
         self,
         domain::{
-            namespaces::{ChainId, ChainType, Chains, Method, NamespaceName, SolanaMethod},
+            namespaces::{ChainId, ChainType, Chains, NamespaceName},
             Pairing,
             ProjectId,
         },
         init_tracing,
-        rpc::{RequestMethod, RequestParams, SessionRequestRequest},
         ClientSession,
         Dapp,
         KvStorage,
@@ -16,7 +15,6 @@ use {
         NoopSessionHandler,
         ReownBuilder,
     },
-    serde_json::json,
     std::time::Duration,
     tokio::{select, signal},
     tracing::{error, info},
@@ -51,37 +49,27 @@ async fn pair_ping(dapp: Dapp) {
 }
 
 async fn sign_message(session: ClientSession) {
-    if !session.namespaces().0.contains_key(&NamespaceName::Solana) {
+    let namespaces = session.namespaces();
+    let Some(sol_namespace) = namespaces.0.get(&NamespaceName::Solana) else {
         return;
-    }
-    let sol_namespace = session.namespaces().0.get(&NamespaceName::Solana).unwrap();
+    };
     for a in &sol_namespace.accounts.0 {
         let addr = &a.address;
         info!("found solana address {addr}");
-        let params: RequestParams = RequestParams::SessionRequest(SessionRequestRequest {
-            request: RequestMethod {
-                method: Method::Solana(SolanaMethod::SignMessage),
-                params: json!({
-                    "message": "37u9WtQpcm6ULa3VtWDFAWoQc1hUvybPrA3dtx99tgHvvcE7pKRZjuGmn7VX2tC3JmYDYGG7",
-                    "pubkey": addr,
-                }),
-                expiry: None,
-            },
-            chain_id: a.chain.clone(),
-        });
-        info!(
-            "signing a personal message\n{}",
-            serde_json::to_string_pretty(&params).unwrap()
-        );
-        match session.publish_request::<serde_json::Value>(params).await {
+        let ChainId::Solana(chain) = a.chain else {
+            continue;
+        };
+        info!("signing a personal message on {}", a.chain);
+        match session
+            .solana(chain)
+            .sign_message(b"hello from monedero-mesh", addr)
+            .await
+        {
             Err(e) => {
                 error!("failed to publish message! {e}");
             }
-            Ok(r) => {
-                info!(
-                    "got back signature response\n{:?}",
-                    serde_json::to_string_pretty(&r).unwrap()
-                );
+            Ok(signature) => {
+                info!("got back signature {signature}");
             }
         };
     }
@@ -48,11 +48,11 @@ pub enum Event {
 impl From<&NamespaceName> for Events {
     fn from(value: &NamespaceName) -> Self {
         match value {
-            NamespaceName::EIP155 => Self(BTreeSet::from([
+            NamespaceName::EIP155 | NamespaceName::Solana => Self(BTreeSet::from([
                 Event::AccountsChanged,
                 Event::ChainChanged,
             ])),
-            _ => Self(BTreeSet::new()),
+            NamespaceName::Other(_) => Self(BTreeSet::new()),
         }
     }
 }
@@ -126,6 +126,43 @@ where
 }
 
 impl Namespaces {
+    /// Request `methods` on top of the defaults, e.g.
+    /// [`EipMethod::eip5792`]. Methods of a namespace without chains are
+    /// ignored.
+    #[must_use]
+    pub fn with_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        for method in methods {
+            let name = match &method {
+                Method::EIP155(_) => NamespaceName::EIP155,
+                Method::Solana(_) => NamespaceName::Solana,
+                Method::Other(_) => continue,
+            };
+            if let Some(ns) = self.0.get_mut(&name) {
+                ns.methods.0.insert(method);
+            }
+        }
+        self
+    }
+
+    /// True if these namespaces keep every chain, method and event of
+    /// `required`, and every account is on a chain of its namespace.
+    #[must_use]
+    pub fn satisfies(&self, required: &Self) -> bool {
+        let accounts_listed = self.0.values().all(|ns| {
+            ns.accounts
+                .iter()
+                .all(|account| ns.chains.contains(&account.chain))
+        });
+        accounts_listed
+            && required.0.iter().all(|(name, required)| {
+                self.0.get(name).is_some_and(|ns| {
+                    required.chains.is_subset(&ns.chains)
+                        && required.methods.0.is_subset(&ns.methods.0)
+                        && required.events.is_subset(&ns.events)
+                })
+            })
+    }
+
     #[must_use]
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
@@ -171,6 +208,8 @@ mod tests {
             Method::EIP155(EipMethod::SignTypedDataV4),
             Method::EIP155(EipMethod::SignTypedData),
             Method::EIP155(EipMethod::Sign),
+            Method::EIP155(EipMethod::SwitchEthereumChain),
+            Method::EIP155(EipMethod::AddEthereumChain),
         ]);
         assert_eq!(eip_default_methods, eip_ns.methods.0);
         let expected_json = json!(
@@ -186,7 +225,9 @@ mod tests {
               "eth_signTransaction",
               "eth_signTypedData",
               "eth_signTypedData_v4",
-              "personal_sign"
+              "personal_sign",
+              "wallet_addEthereumChain",
+              "wallet_switchEthereumChain"
             ],
            "events": [
               "accountsChanged",
@@ -197,8 +238,14 @@ mod tests {
             "chains": [
               "solana:4sGjMW1sUnHzSxGspuhpqLDx6wiyjNtZ"
             ],
-            "events": [],
+            "events": [
+              "accountsChanged",
+              "chainChanged"
+            ],
             "methods": [
+              "solana_getAccounts",
+              "solana_signAllTransactions",
+              "solana_signAndSendTransaction",
               "solana_signMessage",
               "solana_signTransaction"
             ]
@@ -209,6 +256,15 @@ mod tests {
         let result = serde_json::to_value(&namespaces)?;
         // eprintln!("{}", serde_json::to_string_pretty(&expected_json)?);
         assert_eq!(expected_json, result);
+
+        let namespaces = namespaces.with_methods(EipMethod::eip5792());
+        let eip_ns = namespaces
+            .get(&NamespaceName::EIP155)
+            .ok_or(Error::NamespaceNotFound)?;
+        assert!(eip_ns
+            .methods
+            .contains(&Method::EIP155(EipMethod::SendCalls)));
+        assert!(eip_ns.methods.is_superset(&EipMethod::defaults()));
         Ok(())
     }
 
@@ -336,4 +392,27 @@ mod tests {
         assert!(found.is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_satisfies() {
+        let eth = ChainId::EIP155(alloy_chains::Chain::mainnet());
+        let base = ChainId::EIP155(alloy_chains::Chain::base_mainnet());
+        let required = Namespaces::from(std::slice::from_ref(&eth));
+        let mut updated = Namespaces::from(&[eth, base]);
+        assert!(updated.satisfies(&required));
+        assert!(!required.satisfies(&updated));
+
+        let eip = updated.get_mut(&NamespaceName::EIP155).expect("eip155");
+        eip.accounts.0.insert(Account {
+            address: String::from("0xBA5BA3955463ADcc7aa3E33bbdfb8A68e0933dD8"),
+            chain: ChainId::Solana(ChainType::Main),
+        });
+        assert!(!updated.satisfies(&required));
+
+        let mut updated = required.clone();
+        let eip = updated.get_mut(&NamespaceName::EIP155).expect("eip155");
+        eip.methods.0.remove(&Method::EIP155(EipMethod::PersonalSign));
+        assert!(!updated.satisfies(&required));
+        assert!(!Namespaces::default().satisfies(&required));
+    }
 }
@@ -17,6 +17,17 @@ const SIGN_TYPED_DATA: &str = "eth_signTypedData";
 const SIGN_TYPED_DAVA_V4: &str = "eth_signTypedData_v4";
 const SEND_TRANSACTION: &str = "eth_sendTransaction";
 const SEND_TRANSACTION_EXT: &str = "eth_sendTransactionExt";
+const SIGN_TYPED_DATA_V3: &str = "eth_signTypedData_v3";
+const ACCOUNTS: &str = "eth_accounts";
+const REQUEST_ACCOUNTS: &str = "eth_requestAccounts";
+const CHAIN_ID: &str = "eth_chainId";
+const SWITCH_ETHEREUM_CHAIN: &str = "wallet_switchEthereumChain";
+const ADD_ETHEREUM_CHAIN: &str = "wallet_addEthereumChain";
+const WATCH_ASSET: &str = "wallet_watchAsset";
+const GET_CAPABILITIES: &str = "wallet_getCapabilities";
+const SEND_CALLS: &str = "wallet_sendCalls";
+const GET_CALLS_STATUS: &str = "wallet_getCallsStatus";
+const SHOW_CALLS_STATUS: &str = "wallet_showCallsStatus";
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SerializeDisplay, DeserializeFromStr)]
 pub enum EipMethod {
@@ -28,6 +39,17 @@ pub enum EipMethod {
     SignTypedDataV4,
     SendTransaction,
     SendTransactionExt,
+    SignTypedDataV3,
+    Accounts,
+    RequestAccounts,
+    ChainId,
+    SwitchEthereumChain,
+    AddEthereumChain,
+    WatchAsset,
+    GetCapabilities,
+    SendCalls,
+    GetCallsStatus,
+    ShowCallsStatus,
     Other(String),
 }
 
@@ -54,12 +76,24 @@ impl Display for EipMethod {
             Self::SignTypedDataV4 => write!(f, "{SIGN_TYPED_DAVA_V4}"),
             Self::SendTransaction => write!(f, "{SEND_TRANSACTION}"),
             Self::SendTransactionExt => write!(f, "{SEND_TRANSACTION_EXT}"),
+            Self::SignTypedDataV3 => write!(f, "{SIGN_TYPED_DATA_V3}"),
+            Self::Accounts => write!(f, "{ACCOUNTS}"),
+            Self::RequestAccounts => write!(f, "{REQUEST_ACCOUNTS}"),
+            Self::ChainId => write!(f, "{CHAIN_ID}"),
+            Self::SwitchEthereumChain => write!(f, "{SWITCH_ETHEREUM_CHAIN}"),
+            Self::AddEthereumChain => write!(f, "{ADD_ETHEREUM_CHAIN}"),
+            Self::WatchAsset => write!(f, "{WATCH_ASSET}"),
+            Self::GetCapabilities => write!(f, "{GET_CAPABILITIES}"),
+            Self::SendCalls => write!(f, "{SEND_CALLS}"),
+            Self::GetCallsStatus => write!(f, "{GET_CALLS_STATUS}"),
+            Self::ShowCallsStatus => write!(f, "{SHOW_CALLS_STATUS}"),
             Self::Other(m) => write!(f, "{m}"),
         }
     }
 }
 
 impl EipMethod {
+    /// Signing, sending and chain switching.
     #[must_use]
     pub fn defaults() -> BTreeSet<Method> {
         BTreeSet::from([
@@ -69,8 +103,44 @@ impl EipMethod {
             Method::EIP155(Self::SignTypedDataV4),
             Method::EIP155(Self::SignTypedData),
             Method::EIP155(Self::Sign),
+            Method::EIP155(Self::SwitchEthereumChain),
+            Method::EIP155(Self::AddEthereumChain),
         ])
     }
+
+    /// Account, chain and asset methods of the injected provider.
+    #[must_use]
+    pub fn wallet() -> BTreeSet<Method> {
+        BTreeSet::from([
+            Method::EIP155(Self::Accounts),
+            Method::EIP155(Self::RequestAccounts),
+            Method::EIP155(Self::ChainId),
+            Method::EIP155(Self::SwitchEthereumChain),
+            Method::EIP155(Self::AddEthereumChain),
+            Method::EIP155(Self::WatchAsset),
+        ])
+    }
+
+    /// [EIP-5792](https://eips.ethereum.org/EIPS/eip-5792) wallet call batching.
+    #[must_use]
+    pub fn eip5792() -> BTreeSet<Method> {
+        BTreeSet::from([
+            Method::EIP155(Self::GetCapabilities),
+            Method::EIP155(Self::SendCalls),
+            Method::EIP155(Self::GetCallsStatus),
+            Method::EIP155(Self::ShowCallsStatus),
+        ])
+    }
+
+    /// Every known method, including the legacy `eth_signTypedData_v3`.
+    #[must_use]
+    pub fn all() -> BTreeSet<Method> {
+        let mut all = Self::defaults();
+        all.extend(Self::wallet());
+        all.extend(Self::eip5792());
+        all.insert(Method::EIP155(Self::SignTypedDataV3));
+        all
+    }
 }
 
 impl FromStr for EipMethod {
@@ -86,6 +156,17 @@ impl FromStr for EipMethod {
             SIGN_TYPED_DAVA_V4 => Ok(Self::SignTypedDataV4),
             SEND_TRANSACTION => Ok(Self::SendTransaction),
             SEND_TRANSACTION_EXT => Ok(Self::SendTransactionExt),
+            SIGN_TYPED_DATA_V3 => Ok(Self::SignTypedDataV3),
+            ACCOUNTS => Ok(Self::Accounts),
+            REQUEST_ACCOUNTS => Ok(Self::RequestAccounts),
+            CHAIN_ID => Ok(Self::ChainId),
+            SWITCH_ETHEREUM_CHAIN => Ok(Self::SwitchEthereumChain),
+            ADD_ETHEREUM_CHAIN => Ok(Self::AddEthereumChain),
+            WATCH_ASSET => Ok(Self::WatchAsset),
+            GET_CAPABILITIES => Ok(Self::GetCapabilities),
+            SEND_CALLS => Ok(Self::SendCalls),
+            GET_CALLS_STATUS => Ok(Self::GetCallsStatus),
+            SHOW_CALLS_STATUS => Ok(Self::ShowCallsStatus),
             _ => Ok(Self::Other(String::from(s))),
         }
     }
@@ -110,6 +191,18 @@ mod tests {
             EipMethod::SendTransactionExt,
             SEND_TRANSACTION_EXT.parse::<EipMethod>()?
         );
+        assert_eq!(
+            EipMethod::SwitchEthereumChain,
+            SWITCH_ETHEREUM_CHAIN.parse::<EipMethod>()?
+        );
+        assert_eq!(EipMethod::SendCalls, SEND_CALLS.parse::<EipMethod>()?);
+        assert_eq!(
+            EipMethod::SignTypedDataV3,
+            SIGN_TYPED_DATA_V3.parse::<EipMethod>()?
+        );
+        for m in EipMethod::all() {
+            assert_eq!(m, m.to_string().parse::<Method>()?);
+        }
         assert!(matches!(
             "eth_signAndSend".parse::<EipMethod>()?,
             EipMethod::Other(_)
@@ -13,11 +13,17 @@ use {
 
 const SIGN_MESSAGE: &str = "solana_signMessage";
 const SIGN_TRANSACTION: &str = "solana_signTransaction";
+const SIGN_ALL_TRANSACTIONS: &str = "solana_signAllTransactions";
+const SIGN_AND_SEND_TRANSACTION: &str = "solana_signAndSendTransaction";
+const GET_ACCOUNTS: &str = "solana_getAccounts";
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SerializeDisplay, DeserializeFromStr)]
 pub enum SolanaMethod {
     SignMessage,
     SignTransaction,
+    SignAllTransactions,
+    SignAndSendTransaction,
+    GetAccounts,
     Other(String),
 }
 
@@ -38,6 +44,9 @@ impl Display for SolanaMethod {
         match self {
             Self::SignMessage => write!(f, "{SIGN_MESSAGE}"),
             Self::SignTransaction => write!(f, "{SIGN_TRANSACTION}"),
+            Self::SignAllTransactions => write!(f, "{SIGN_ALL_TRANSACTIONS}"),
+            Self::SignAndSendTransaction => write!(f, "{SIGN_AND_SEND_TRANSACTION}"),
+            Self::GetAccounts => write!(f, "{GET_ACCOUNTS}"),
             Self::Other(m) => write!(f, "{m}"),
         }
     }
@@ -50,6 +59,9 @@ impl FromStr for SolanaMethod {
         match s {
             SIGN_TRANSACTION => Ok(Self::SignTransaction),
             SIGN_MESSAGE => Ok(Self::SignMessage),
+            SIGN_ALL_TRANSACTIONS => Ok(Self::SignAllTransactions),
+            SIGN_AND_SEND_TRANSACTION => Ok(Self::SignAndSendTransaction),
+            GET_ACCOUNTS => Ok(Self::GetAccounts),
             _ => Ok(Self::Other(s.to_string())),
         }
     }
@@ -61,6 +73,9 @@ impl SolanaMethod {
         BTreeSet::from([
             Method::Solana(Self::SignTransaction),
             Method::Solana(Self::SignMessage),
+            Method::Solana(Self::SignAllTransactions),
+            Method::Solana(Self::SignAndSendTransaction),
+            Method::Solana(Self::GetAccounts),
         ])
     }
 }
@@ -79,6 +94,21 @@ mod tests {
             SolanaMethod::SignMessage,
             SIGN_MESSAGE.parse::<SolanaMethod>()?
         );
+        assert_eq!(
+            SolanaMethod::SignAndSendTransaction,
+            SIGN_AND_SEND_TRANSACTION.parse::<SolanaMethod>()?
+        );
+        assert_eq!(
+            SolanaMethod::SignAllTransactions,
+            SIGN_ALL_TRANSACTIONS.parse::<SolanaMethod>()?
+        );
+        assert_eq!(
+            SolanaMethod::GetAccounts,
+            GET_ACCOUNTS.parse::<SolanaMethod>()?
+        );
+        for m in SolanaMethod::defaults() {
+            assert_eq!(m, m.to_string().parse::<Method>()?);
+        }
         assert!(matches!(
             "solana_signAndSend".parse::<SolanaMethod>()?,
             SolanaMethod::Other(_)
@@ -0,0 +1,286 @@
+//! Runs a [`MockRelay`] as a standalone server, so clients in other languages
+//! can be tested against the same deterministic relay.
+//!
+//! The admin endpoint answers plain HTTP:
+//!
+//! - `GET /` the whole [`monedero_relay::MockRelayState`]
+//! - `GET /clients`, `GET /topics`, `GET /pending`
+//! - `PUT /faults/<topic>/<fault>` where topic `*` means every topic
+//! - `DELETE /faults/<topic>` and `DELETE /faults`
+use {
+    clap::Parser,
+    monedero_relay::{MockRelay, ProjectId, PublishFault, Topic},
+    std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, sync::Arc},
+    tokio::{
+        io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    },
+    tracing::{info, warn},
+    tracing_subscriber::EnvFilter,
+};
+
+const ANY_TOPIC: &str = "*";
+
+#[derive(Parser)]
+#[command(version, about = "Deterministic WalletConnect relay for testing")]
+struct Args {
+    /// Address the relay listens on
+    #[arg(
+        long,
+        short,
+        env = "MOCK_RELAY_LISTEN",
+        default_value = "127.0.0.1:4001"
+    )]
+    listen: SocketAddr,
+
+    /// Address of the admin HTTP endpoint
+    #[arg(long, env = "MOCK_RELAY_ADMIN", default_value = "127.0.0.1:4002")]
+    admin: SocketAddr,
+
+    /// Log filter, e.g. `info` or `monedero_relay=debug`
+    #[arg(long, env = "RUST_LOG", default_value = "info")]
+    log: String,
+
+    /// Also accept JWTs issued for this audience, e.g. the relay URL your
+    /// client is configured with
+    #[arg(long)]
+    audience: Vec<String>,
+
+    /// Only accept these project IDs
+    #[arg(long = "project-id")]
+    project_ids: Vec<String>,
+
+    /// Reject publishes with a larger message, in bytes
+    #[arg(long)]
+    max_message_size: Option<usize>,
+
+    /// Append all traffic to this JSONL file
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Publish fault as TOPIC=FAULT, use `*` for every topic. FAULT is one of
+    /// drop, delay:<ms>, duplicate:<n>, reorder or reject:<code>
+    #[arg(long = "fault", value_parser = parse_fault)]
+    faults: Vec<(String, PublishFault)>,
+}
+
+fn parse_fault(s: &str) -> Result<(String, PublishFault), String> {
+    let (topic, fault) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected TOPIC=FAULT, got '{s}'"))?;
+    Ok((String::from(topic), fault.parse()?))
+}
+
+fn set_fault(relay: &MockRelay, topic: &str, fault: PublishFault) {
+    if topic == ANY_TOPIC {
+        relay.faults().publish_default(Some(fault));
+    } else {
+        relay.faults().publish(Topic::new(Arc::from(topic)), fault);
+    }
+}
+
+fn json<T: serde::Serialize>(value: &T) -> (&'static str, String) {
+    match serde_json::to_string_pretty(value) {
+        Ok(body) => ("200 OK", body),
+        Err(e) => (
+            "500 Internal Server Error",
+            format!("{{\"error\":\"{e}\"}}"),
+        ),
+    }
+}
+
+fn route(relay: &MockRelay, method: &str, path: &str) -> (&'static str, String) {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        ("GET", [""]) => json(&relay.state()),
+        ("GET", ["clients"]) => json(&relay.state().clients),
+        ("GET", ["pending"]) => json(&relay.state().pending),
+        ("GET", ["topics"]) => {
+            let mut topics: BTreeMap<String, Vec<u16>> = BTreeMap::new();
+            for client in relay.state().clients {
+                for topic in client.topics {
+                    topics.entry(topic.to_string()).or_default().push(client.id);
+                }
+            }
+            json(&topics)
+        }
+        ("PUT", ["faults", topic, fault]) => match fault.parse::<PublishFault>() {
+            Ok(fault) => {
+                info!("fault on {topic}: {fault:?}");
+                set_fault(relay, topic, fault);
+                ("204 No Content", String::new())
+            }
+            Err(e) => ("400 Bad Request", format!("{{\"error\":\"{e}\"}}")),
+        },
+        ("DELETE", ["faults", topic]) => {
+            if *topic == ANY_TOPIC {
+                relay.faults().publish_default(None);
+            } else {
+                relay.faults().clear(&Topic::new(Arc::from(*topic)));
+            }
+            ("204 No Content", String::new())
+        }
+        ("DELETE", ["faults"]) => {
+            relay.faults().clear_all();
+            ("204 No Content", String::new())
+        }
+        _ => ("404 Not Found", String::new()),
+    }
+}
+
+/// Largest admin request accepted, headers and body.
+const MAX_REQUEST: usize = 64 * 1024;
+
+/// A request of the admin endpoint, no route takes a body so it is skipped.
+struct Request {
+    method: String,
+    path: String,
+}
+
+fn header_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4)
+}
+
+/// Reads until the end of the headers, then discards `content-length` more
+/// bytes.
+async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Request> {
+    let too_large = || std::io::Error::new(std::io::ErrorKind::InvalidData, "request too large");
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+    let end = loop {
+        if let Some(end) = header_end(&buf) {
+            break end;
+        }
+        if buf.len() > MAX_REQUEST {
+            return Err(too_large());
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = String::from(request_line.next().unwrap_or_default());
+    let path = String::from(request_line.next().unwrap_or_default());
+    let length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+    if end + length > MAX_REQUEST {
+        return Err(too_large());
+    }
+    let mut remaining = length.saturating_sub(buf.len() - end);
+    while remaining > 0 {
+        let n = stream.read(&mut chunk[..remaining.min(4096)]).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        remaining -= n;
+    }
+    Ok(Request { method, path })
+}
+
+async fn handle_admin(mut stream: TcpStream, relay: MockRelay) -> std::io::Result<()> {
+    let (status, body) = match read_request(&mut stream).await {
+        Ok(request) => route(&relay, &request.method, &request.path),
+        Err(e) => ("400 Bad Request", format!("{{\"error\":\"{e}\"}}")),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: \
+         close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn admin(listener: TcpListener, relay: MockRelay) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let relay = relay.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_admin(stream, relay).await {
+                        warn!("admin request failed {e}");
+                    }
+                });
+            }
+            Err(e) => warn!("admin accept failed {e}"),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_target(true)
+        .with_env_filter(EnvFilter::new(&args.log))
+        .init();
+
+    let mut builder = MockRelay::builder().address(args.listen);
+    if let Some(path) = &args.record {
+        builder = builder.record(path);
+    }
+    for audience in args.audience {
+        builder = builder.audience(audience);
+    }
+    if !args.project_ids.is_empty() {
+        builder = builder.project_ids(
+            args.project_ids
+                .iter()
+                .map(|p| ProjectId::new(Arc::from(p.as_str()))),
+        );
+    }
+    if let Some(size) = args.max_message_size {
+        builder = builder.max_message_size(size);
+    }
+    let relay = builder.build().await?;
+    for (topic, fault) in args.faults {
+        set_fault(&relay, &topic, fault);
+    }
+    let listener = TcpListener::bind(args.admin).await?;
+    info!("admin endpoint on http://{}", listener.local_addr()?);
+    let admin = tokio::spawn(admin(listener, relay.clone()));
+
+    tokio::signal::ctrl_c().await?;
+    info!("shutting down");
+    admin.abort();
+    relay.shutdown().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tokio::io::duplex};
+
+    #[tokio::test]
+    async fn test_read_split_request() -> anyhow::Result<()> {
+        let (mut client, mut server) = duplex(64);
+        let writer = tokio::spawn(async move {
+            for part in [
+                "PUT /faults/*/drop HTTP/1.1\r\nhost: local",
+                "host\r\nContent-Length: 5\r\n\r\nhe",
+                "llo",
+            ] {
+                client.write_all(part.as_bytes()).await?;
+                tokio::task::yield_now().await;
+            }
+            client.write_all(b"next").await?;
+            client.flush().await
+        });
+        let request = read_request(&mut server).await?;
+        writer.await??;
+        assert_eq!("PUT", request.method);
+        assert_eq!("/faults/*/drop", request.path);
+        // the body is consumed, nothing past it
+        let mut rest = [0u8; 4];
+        server.read_exact(&mut rest).await?;
+        assert_eq!(b"next", &rest);
+        Ok(())
+    }
+}
@@ -1,31 +1,48 @@
 use {
-    crate::{ConnectionHandler, ConnectionOptions, Result, SubscriptionId, Topic},
+    crate::{
+        ClientError,
+        CloseFrame,
+        ConnectionHandler,
+        ConnectionOptions,
+        Result,
+        SubscriptionId,
+        Topic,
+    },
     reown_relay_client::{
         websocket::{Client as WcClient, ConnectionHandler as WcHandler, PublishedMessage},
         ConnectionOptions as WcOptions,
     },
     std::{
         fmt::{Debug, Display, Formatter},
-        sync::Arc,
+        sync::{Arc, Mutex, PoisonError},
         time::Duration,
     },
 };
 
-impl From<&ConnectionOptions> for WcOptions {
-    fn from(opts: &ConnectionOptions) -> Self {
-        Self {
+#[cfg(all(feature = "mock", not(target_family = "wasm")))]
+use crate::mock::{LoopbackConnection, SharedHandler};
+
+impl TryFrom<&ConnectionOptions> for WcOptions {
+    type Error = ClientError;
+
+    fn try_from(opts: &ConnectionOptions) -> Result<Self> {
+        Ok(Self {
             address: String::from(&opts.address),
             project_id: opts.project_id.clone(),
-            auth: opts.auth.clone(),
-            origin: None,
-            user_agent: None,
-        }
+            auth: opts.auth.clone().ok_or(ClientError::MissingAuth)?,
+            origin: opts.origin.clone(),
+            user_agent: opts.user_agent.clone(),
+        })
     }
 }
 
 #[derive(Clone)]
 pub struct Client {
     wc: WcClient,
+    #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+    handler: SharedHandler,
+    #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+    loopback: Arc<Mutex<Option<LoopbackConnection>>>,
 }
 
 impl Debug for Client {
@@ -40,43 +57,75 @@ impl Display for Client {
     }
 }
 
+/// The handler is shared between the websocket client and, with the `mock`
+/// feature, the loopback relay
 struct WrapperHandler<T: ConnectionHandler> {
-    handler: T,
+    handler: Arc<Mutex<T>>,
 }
 
 impl<T: ConnectionHandler> WrapperHandler<T> {
-    const fn new(handler: T) -> Self {
-        Self { handler }
+    fn handler(&self) -> std::sync::MutexGuard<'_, T> {
+        self.handler.lock().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
 impl<T: ConnectionHandler> WcHandler for WrapperHandler<T> {
     fn connected(&mut self) {
-        self.handler.connected();
+        self.handler().connected();
     }
 
-    fn disconnected(&mut self, _frame: Option<reown_relay_client::websocket::CloseFrame<'static>>) {
-        self.handler.disconnected(None);
+    fn disconnected(&mut self, frame: Option<reown_relay_client::websocket::CloseFrame<'static>>) {
+        self.handler().disconnected(frame.map(|f| CloseFrame {
+            code: u16::from(f.code),
+            reason: f.reason,
+        }));
     }
 
     fn message_received(&mut self, message: PublishedMessage) {
-        self.handler.message_received(message.into());
+        self.handler().message_received(message.into());
     }
 
     fn inbound_error(&mut self, err: reown_relay_client::error::ClientError) {
-        self.handler.inbound_error(err.into());
+        self.handler().inbound_error(err.into());
     }
 
     fn outbound_error(&mut self, err: reown_relay_client::error::ClientError) {
-        self.handler.outbound_error(err.into());
+        self.handler().outbound_error(err.into());
     }
 }
 
 impl Client {
     pub fn new(handler: impl ConnectionHandler) -> Self {
-        let wrapper = WrapperHandler::new(handler);
-        let wc = WcClient::new(wrapper);
-        Self { wc }
+        let handler = Arc::new(Mutex::new(handler));
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        let shared: SharedHandler = handler.clone();
+        let wc = WcClient::new(WrapperHandler { handler });
+        Self {
+            wc,
+            #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+            handler: shared,
+            #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+            loopback: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+    fn loopback(&self) -> Option<LoopbackConnection> {
+        self.loopback
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+    fn set_loopback(&self, conn: Option<LoopbackConnection>) {
+        let old = std::mem::replace(
+            &mut *self.loopback.lock().unwrap_or_else(PoisonError::into_inner),
+            conn,
+        );
+        if let Some(old) = old {
+            old.close();
+        }
     }
 }
 
@@ -90,6 +139,13 @@ impl Client {
         ttl: Duration,
         prompt: bool,
     ) -> Result<()> {
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        if let Some(conn) = self.loopback() {
+            return conn
+                .publish(topic, message.into(), tag, ttl, prompt)
+                .ok_or(ClientError::Disconnected)?
+                .map_err(|e| ClientError::NetworkError(e.into()));
+        }
         self.wc
             .publish(topic, message, None, tag, ttl, prompt)
             .await?;
@@ -99,6 +155,13 @@ impl Client {
     /// Subscribes on topic to receive messages.
     /// The request is resolved optimistically as soon as the relay receives it.
     pub async fn subscribe(&self, topic: Topic) -> Result<SubscriptionId> {
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        if let Some(conn) = self.loopback() {
+            return conn
+                .subscribe(&topic)
+                .ok_or(ClientError::Disconnected)?
+                .map_err(|e| ClientError::NetworkError(e.into()));
+        }
         let id = self.wc.subscribe(topic).await?;
         Ok(id)
     }
@@ -109,29 +172,56 @@ impl Client {
         &self,
         topics: impl Into<Vec<Topic>> + Send,
     ) -> Result<Vec<SubscriptionId>> {
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        if let Some(conn) = self.loopback() {
+            return topics
+                .into()
+                .iter()
+                .map(|t| conn.subscribe(t))
+                .collect::<Option<std::result::Result<Vec<_>, _>>>()
+                .ok_or(ClientError::Disconnected)?
+                .map_err(|e| ClientError::NetworkError(e.into()));
+        }
         let topics = self.wc.batch_subscribe(topics).await?;
         Ok(topics)
     }
 
     /// Unsubscribes from a topic
     pub async fn unsubscribe(&self, topic: Topic) -> Result<()> {
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        if let Some(conn) = self.loopback() {
+            return conn
+                .unsubscribe(&topic)
+                .ok_or(ClientError::Disconnected)?
+                .map_err(|e| ClientError::NetworkError(e.into()));
+        }
         self.wc.unsubscribe(topic).await?;
         Ok(())
     }
 
     pub async fn connect(&self, opts: &ConnectionOptions) -> Result<()> {
-        let wc: WcOptions = WcOptions {
-            address: String::from(&opts.address),
-            project_id: opts.project_id.clone(),
-            auth: opts.auth.clone(),
-            origin: None,
-            user_agent: None,
-        };
-        self.wc.connect(&wc).await?;
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        if let Some(relay) = &opts.loopback {
+            let conn = relay
+                .connect(self.handler.clone(), opts)
+                .map_err(|e| ClientError::NetworkError(e.into()))?;
+            self.set_loopback(Some(conn));
+            self.handler
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .connected();
+            return Ok(());
+        }
+        self.wc.connect(&WcOptions::try_from(opts)?).await?;
         Ok(())
     }
 
     pub async fn disconnect(&self) -> Result<()> {
+        #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+        if self.loopback().is_some() {
+            self.set_loopback(None);
+            return Ok(());
+        }
         self.wc.disconnect().await.ok();
         Ok(())
     }
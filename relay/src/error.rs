@@ -27,9 +27,34 @@ pub enum ClientError {
     #[error("failed to generate jwt key")]
     JwtError,
 
+    #[error("no auth token in the connection options")]
+    MissingAuth,
+
     #[error(transparent)]
     PublicationError(#[from] reown_relay_client::error::Error<PublishError>),
 
     #[error(transparent)]
     BindError(#[from] tokio::io::Error),
 }
+
+impl ClientError {
+    /// The relay refused the credentials, either while connecting or by
+    /// closing the connection. Retrying with the same token will not help.
+    pub fn is_auth_failure(&self) -> bool {
+        use reown_relay_client::{error::ClientError as WcError, websocket::WebsocketClientError};
+        let Self::NetworkError(WcError::WebsocketClient(e)) = self else {
+            return false;
+        };
+        match e {
+            WebsocketClientError::ConnectionClosed(reason) => reason
+                .0
+                .as_ref()
+                .is_some_and(|f| crate::is_auth_close(u16::from(f.code))),
+            #[cfg(not(target_family = "wasm"))]
+            WebsocketClientError::ConnectionFailed(
+                tokio_tungstenite::tungstenite::Error::Http(response),
+            ) => matches!(response.status().as_u16(), 401 | 403),
+            _ => false,
+        }
+    }
+}
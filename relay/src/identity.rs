@@ -0,0 +1,125 @@
+use {
+    crate::AUTH_TTL,
+    reown_relay_rpc::{
+        auth::{ed25519_dalek::SigningKey, AuthToken, SerializedAuthToken},
+        domain::{ClientId, DecodedClientId},
+    },
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::{Debug, Formatter},
+};
+
+/// Long lived ed25519 key that identifies this client to the relay.
+///
+/// The relay knows a client by the `did:key` in the `iss` claim of its auth
+/// JWT. Reusing the same identity across restarts lets the relay treat the
+/// client as the same peer for message delivery and rate limiting.
+#[derive(Clone)]
+pub struct ClientIdentity {
+    key: SigningKey,
+}
+
+impl Debug for ClientIdentity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[identity {}]", self.did_key())
+    }
+}
+
+impl PartialEq for ClientIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        self.key.verifying_key() == other.key.verifying_key()
+    }
+}
+
+impl Eq for ClientIdentity {}
+
+impl ClientIdentity {
+    pub fn generate() -> Self {
+        Self {
+            key: SigningKey::generate(&mut rand::thread_rng()),
+        }
+    }
+
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    pub fn client_id(&self) -> ClientId {
+        ClientId::from(DecodedClientId::from_key(&self.key.verifying_key()))
+    }
+
+    /// The `did:key:` form of the public key.
+    pub fn did_key(&self) -> String {
+        DecodedClientId::from_key(&self.key.verifying_key()).to_did_key()
+    }
+
+    /// Mint a relay JWT for `url`, valid for [`AUTH_TTL`]. Only the relay at
+    /// `audience` accepts it.
+    pub fn auth_token(
+        &self,
+        url: impl Into<String>,
+        audience: impl Into<String>,
+    ) -> Result<SerializedAuthToken, reown_relay_rpc::auth::Error> {
+        AuthToken::new(url)
+            .aud(audience)
+            .ttl(AUTH_TTL)
+            .as_jwt(&self.key)
+    }
+}
+
+/// Only the secret is stored, the public key is derived from it.
+#[derive(Serialize, Deserialize)]
+struct StoredIdentity {
+    secret: [u8; 32],
+}
+
+impl Serialize for ClientIdentity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredIdentity {
+            secret: self.key.to_bytes(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClientIdentity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = StoredIdentity::deserialize(deserializer)?;
+        Ok(Self::from_bytes(&stored.secret))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::RELAY_ADDRESS,
+        reown_relay_rpc::jwt::{JwtBasicClaims, VerifyableClaims},
+        std::collections::HashSet,
+    };
+
+    #[test]
+    fn identity_roundtrip() -> anyhow::Result<()> {
+        let identity = ClientIdentity::generate();
+        let stored = serde_json::to_string(&identity)?;
+        let restored: ClientIdentity = serde_json::from_str(&stored)?;
+        assert_eq!(identity, restored);
+        assert_eq!(identity.client_id(), restored.client_id());
+        assert!(identity.did_key().starts_with("did:key:z"));
+
+        let token = restored.auth_token(crate::AUTH_URL, RELAY_ADDRESS)?;
+        let claims = JwtBasicClaims::try_from_str(&token.to_string())?;
+        claims.verify_basic(&HashSet::from([String::from(RELAY_ADDRESS)]), None)?;
+        assert_eq!(identity.client_id(), ClientId::from(claims.iss));
+
+        let other = "wss://relay.example.com";
+        let token = identity.auth_token(crate::AUTH_URL, other)?;
+        let claims = JwtBasicClaims::try_from_str(&token.to_string())?;
+        assert!(claims
+            .verify_basic(&HashSet::from([String::from(RELAY_ADDRESS)]), None)
+            .is_err());
+        claims.verify_basic(&HashSet::from([String::from(other)]), None)?;
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ pub use {
     reown_relay_rpc::{
         auth::*,
         domain::{
+            ClientId,
             ClientIdDecodingError,
             DecodedTopic,
             MessageId,
@@ -10,12 +11,12 @@ pub use {
             SubscriptionId,
             Topic,
         },
+        rpc::{Error as RelayRpcError, ErrorData, PublishError, SubscriptionError},
         user_agent::*,
     },
 };
 use {
     reown_relay_client::{websocket::PublishedMessage, Authorization},
-    reown_relay_rpc::auth::ed25519_dalek::SigningKey,
     serde::{Deserialize, Serialize},
     std::{
         borrow::Cow,
@@ -29,17 +30,36 @@ pub const RELAY_ADDRESS: &str = "wss://relay.walletconnect.com";
 pub(crate) const MOCK_RELAY_ADDRESS: &str = "127.0.0.1:4001";
 pub const RELAY_PROTOCOL: &str = "irn";
 pub const AUTH_URL: &str = "https://cartera-mesh.com";
+/// Lifetime of the relay JWTs minted by this crate.
+pub const AUTH_TTL: Duration = Duration::from_hours(1);
 
 mod client;
 mod error;
+mod identity;
 #[cfg(not(target_family = "wasm"))]
 mod mock;
 pub use client::Client;
+#[cfg(all(feature = "mock", not(target_family = "wasm")))]
+pub use mock::LoopbackRelay;
 #[cfg(not(target_family = "wasm"))]
-pub use mock::MockRelay;
+pub use mock::{
+    MockClientState,
+    MockFaults,
+    MockPendingState,
+    MockRelay,
+    MockRelayBuilder,
+    MockRelayState,
+    PublishFault,
+    TrafficKind,
+    TrafficRecord,
+    TrafficRecorder,
+    MAX_MESSAGE_SIZE,
+    MAX_TTL,
+    MIN_TTL,
+};
 pub type PairingTopic = Topic;
 pub type SessionTopic = Topic;
-pub use error::ClientError;
+pub use {error::ClientError, identity::ClientIdentity};
 pub type Result<T> = std::result::Result<T, ClientError>;
 
 pub fn shorten_topic(id: &Topic) -> String {
@@ -128,14 +148,19 @@ pub struct ConnectionOptions {
     /// at the following URL: <https://cloud.walletconnect.com/app>
     pub project_id: ProjectId,
 
-    /// The authorization method and auth token to use.
-    #[allow(dead_code)]
-    pub(crate) auth: Authorization,
+    /// The authorization method and auth token to use, a connection without
+    /// one fails.
+    pub(crate) auth: Option<Authorization>,
 
     /// Optional origin of the request. Subject to allow-list validation.
     pub origin: Option<String>,
 
     pub user_agent: Option<UserAgent>,
+
+    /// Route the connection to an in-process [`LoopbackRelay`] instead of a
+    /// websocket.
+    #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+    pub(crate) loopback: Option<LoopbackRelay>,
 }
 
 impl ConnectionOptions {
@@ -151,24 +176,84 @@ impl ConnectionOptions {
         Self::create(RELAY_ADDRESS, project_id, serialized)
     }
 
-    fn create(address: &str, project_id: ProjectId, serialized: SerializedAuthToken) -> Self {
+    pub(crate) fn create(
+        address: &str,
+        project_id: ProjectId,
+        serialized: SerializedAuthToken,
+    ) -> Self {
         Self {
             address: address.into(),
             project_id,
-            auth: Authorization::Query(serialized),
+            auth: Some(Authorization::Query(serialized)),
             origin: None,
-            user_agent: None,
+            user_agent: Some(default_user_agent()),
+            #[cfg(all(feature = "mock", not(target_family = "wasm")))]
+            loopback: None,
         }
     }
+
+    /// Connect to the relay at `address` instead.
+    #[must_use]
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    /// Send `origin` as the `Origin` header, for relays that check it against
+    /// the project's allow-list.
+    #[must_use]
+    pub fn with_origin(mut self, origin: impl Into<Option<String>>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    /// Replace the [`default_user_agent`], or send none with `None`.
+    #[must_use]
+    pub fn with_user_agent(mut self, user_agent: impl Into<Option<UserAgent>>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Replace the auth token, e.g. with a fresh one before the current
+    /// token expires.
+    pub fn set_auth(&mut self, serialized: SerializedAuthToken) {
+        self.auth = Some(Authorization::Query(serialized));
+    }
+
+    /// Drop the auth token, for callers that mint their own, e.g.
+    /// `ReownBuilder` from its client identity.
+    #[must_use]
+    pub fn without_auth(mut self) -> Self {
+        self.auth = None;
+        self
+    }
+
+    pub const fn has_auth(&self) -> bool {
+        self.auth.is_some()
+    }
 }
 
+/// Close code the relay uses when it rejects the JWT.
+pub const CLOSE_UNAUTHORIZED: u16 = 3000;
+/// Websocket close code for a policy violation, e.g. an origin that is not in
+/// the allow-list.
+pub const CLOSE_POLICY_VIOLATION: u16 = 1008;
+
 /// A struct representing the close command.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct CloseFrame<'t> {
+    /// The websocket close code.
+    pub code: u16,
     /// The reason as text string.
     pub reason: Cow<'t, str>,
 }
 
+/// The relay closed the connection with `code` because of the credentials,
+/// so reconnecting with the same ones will fail again.
+pub const fn is_auth_close(code: u16) -> bool {
+    matches!(code, CLOSE_UNAUTHORIZED | CLOSE_POLICY_VIOLATION)
+}
+
 pub struct NoopHandler;
 
 impl ConnectionHandler for NoopHandler {
@@ -213,6 +298,36 @@ pub trait ConnectionHandler: Send + 'static {
     fn outbound_error(&mut self, _error: ClientError) {}
 }
 
+/// User agent sent to the relay, e.g. `wc-2/rust-0.1.0/linux-tokio`.
+pub fn default_user_agent() -> UserAgent {
+    #[cfg(target_family = "wasm")]
+    let (runtime, id) = (
+        "wasm",
+        Some(Id {
+            environment: Environment::Browser,
+            host: None,
+        }),
+    );
+    #[cfg(not(target_family = "wasm"))]
+    let (runtime, id) = ("tokio", None);
+    UserAgent::ValidUserAgent(ValidUserAgent {
+        protocol: Protocol {
+            kind: ProtocolKind::WalletConnect,
+            version: 2,
+        },
+        sdk: Sdk {
+            language: SdkLanguage::Rust,
+            version: String::from(env!("CARGO_PKG_VERSION")),
+        },
+        os: OsInfo {
+            os_family: String::from(std::env::consts::OS),
+            ua_family: Some(String::from(runtime)),
+            version: None,
+        },
+        id,
+    })
+}
+
 pub fn mock_connection_opts(project_id: &ProjectId) -> ConnectionOptions {
     let auth = auth_token(AUTH_URL);
     ConnectionOptions::mock(project_id.clone(), auth)
@@ -228,10 +343,7 @@ pub fn default_connection_opts(project_id: &ProjectId) -> ConnectionOptions {
 /// Will panic when key is invalid
 #[allow(clippy::unwrap_used)]
 pub fn auth_token(url: impl Into<String>) -> SerializedAuthToken {
-    let key = SigningKey::generate(&mut rand::thread_rng());
-    AuthToken::new(url)
-        .aud(RELAY_ADDRESS)
-        .ttl(Duration::from_secs(60 * 60))
-        .as_jwt(&key)
+    ClientIdentity::generate()
+        .auth_token(url, RELAY_ADDRESS)
         .unwrap()
 }
@@ -1,18 +1,36 @@
 use {
-    dashmap::DashSet,
+    crate::Topic,
+    dashmap::DashMap,
     futures_util::stream::SplitSink,
     reown_relay_rpc::rpc::{Payload, Publish},
     std::{
         fmt::{Debug, Display},
         sync::Arc,
+        time::{Duration, Instant},
     },
     tokio::{net::TcpStream, sync::Mutex},
     tokio_tungstenite::{tungstenite::Message, WebSocketStream},
 };
 
+mod builder;
 mod client;
+mod fault;
+#[cfg(feature = "mock")]
+mod loopback;
+mod recorder;
 mod server;
-pub use server::MockRelay;
+mod state;
+mod validate;
+#[cfg(feature = "mock")]
+pub use loopback::{LoopbackConnection, LoopbackRelay, SharedHandler};
+pub use {
+    builder::MockRelayBuilder,
+    fault::{MockFaults, PublishFault},
+    recorder::{TrafficKind, TrafficRecord, TrafficRecorder},
+    server::MockRelay,
+    state::{MockClientState, MockPendingState, MockRelayState},
+    validate::{MAX_MESSAGE_SIZE, MAX_TTL, MIN_TTL},
+};
 
 #[derive(Clone)]
 struct WsPublishedMessage {
@@ -38,7 +56,52 @@ impl Debug for WsPublishedMessage {
     }
 }
 
-type PendingMessages = Arc<DashSet<Publish>>;
+/// Publishes waiting for a subscriber, dropped once their TTL has passed.
+#[derive(Clone, Default)]
+struct PendingMessages {
+    messages: Arc<DashMap<Publish, Instant>>,
+}
+
+impl PendingMessages {
+    fn insert(&self, p: Publish) {
+        let expires = Instant::now() + Duration::from_secs(u64::from(p.ttl_secs));
+        self.messages.insert(p, expires);
+    }
+
+    fn remove(&self, p: &Publish) {
+        self.messages.remove(p);
+    }
+
+    fn expire(&self) {
+        let now = Instant::now();
+        self.messages.retain(|_, expires| *expires > now);
+    }
+
+    /// Removes and returns the unexpired messages for `topic`.
+    fn take(&self, topic: &Topic) -> Vec<Publish> {
+        self.expire();
+        let to_send: Vec<Publish> = self
+            .messages
+            .iter()
+            .filter(|m| m.key().topic == *topic)
+            .map(|m| m.key().clone())
+            .collect();
+        for p in &to_send {
+            self.messages.remove(p);
+        }
+        to_send
+    }
+
+    fn messages(&self) -> Vec<Publish> {
+        self.expire();
+        self.messages.iter().map(|m| m.key().clone()).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.expire();
+        self.messages.len()
+    }
+}
 type WsSender = Arc<Mutex<SplitSink<WebSocketStream<TcpStream>, Message>>>;
 
 #[cfg(test)]
@@ -50,9 +113,13 @@ mod test {
             mock_connection_opts,
             Client,
             ConnectionHandler,
+            ErrorData,
             LogHandler,
             NoopHandler,
             ProjectId,
+            PublishError,
+            RelayRpcError,
+            SubscriptionError,
             Topic,
         },
         std::{collections::VecDeque, time::Duration},
@@ -140,6 +207,520 @@ mod test {
         Ok(())
     }
 
+    struct CloseHandler {
+        closed: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl ConnectionHandler for CloseHandler {
+        fn disconnected(&mut self, _frame: Option<crate::CloseFrame<'static>>) {
+            self.closed
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+
+        fn message_received(&mut self, _message: crate::Message) {}
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn mock_relay_ephemeral() -> anyhow::Result<()> {
+        let relay_1 = MockRelay::builder().ephemeral().build().await?;
+        let relay_2 = MockRelay::builder().ephemeral().build().await?;
+        assert_ne!(relay_1.address(), relay_2.address());
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let messages_1: MockMessages = Arc::new(std::sync::RwLock::new(VecDeque::new()));
+        let client_1 = Client::new(EchoHandler {
+            messages: messages_1.clone(),
+        });
+        let client_2 = Client::new(LogHandler::new(NoopHandler));
+        let closed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let client_3 = Client::new(CloseHandler {
+            closed: closed.clone(),
+        });
+        client_1
+            .connect(&relay_1.connection_opts(&project_id))
+            .await?;
+        client_2
+            .connect(&relay_1.connection_opts(&project_id))
+            .await?;
+        client_3
+            .connect(&relay_2.connection_opts(&project_id))
+            .await?;
+        let topic = Topic::generate();
+        client_1.subscribe(topic.clone()).await?;
+        yield_ms(100).await;
+        client_2
+            .publish(
+                topic,
+                Arc::from("reown the world"),
+                0,
+                Duration::from_secs(30),
+                false,
+            )
+            .await?;
+        yield_ms(200).await;
+        assert_eq!(1, messages_1.read().expect("could not unlock").len());
+        assert_eq!(0, relay_2.pending());
+
+        relay_2.shutdown().await;
+        yield_ms(100).await;
+        assert!(relay_2.clients.is_empty());
+        assert!(closed.load(std::sync::atomic::Ordering::Relaxed));
+        assert!(client_3
+            .connect(&relay_2.connection_opts(&project_id))
+            .await
+            .is_err());
+        // the other instance is unaffected
+        assert!(client_1.subscribe(Topic::generate()).await.is_ok());
+        relay_1.shutdown().await;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn mock_relay_faults() -> anyhow::Result<()> {
+        let relay = MockRelay::builder().ephemeral().build().await?;
+        let faults = relay.faults();
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let messages_1: MockMessages = Arc::new(std::sync::RwLock::new(VecDeque::new()));
+        let client_1 = Client::new(EchoHandler {
+            messages: messages_1.clone(),
+        });
+        let client_2 = Client::new(LogHandler::new(NoopHandler));
+        client_1
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        client_2
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        let topic = Topic::generate();
+        client_1.subscribe(topic.clone()).await?;
+        yield_ms(100).await;
+        let publish = |m: &'static str| {
+            client_2.publish(
+                topic.clone(),
+                Arc::from(m),
+                0,
+                Duration::from_secs(30),
+                false,
+            )
+        };
+        let received = || -> Vec<String> {
+            messages_1
+                .read()
+                .expect("could not unlock")
+                .iter()
+                .map(|m| m.message.to_string())
+                .collect()
+        };
+
+        faults.publish(topic.clone(), PublishFault::Drop);
+        publish("dropped").await?;
+        yield_ms(100).await;
+        assert!(received().is_empty());
+
+        faults.publish(topic.clone(), PublishFault::Duplicate(2));
+        publish("dup").await?;
+        yield_ms(100).await;
+        assert_eq!(vec!["dup"; 3], received());
+        messages_1.write().expect("cannot drain").clear();
+
+        faults.publish(topic.clone(), PublishFault::Reorder);
+        publish("first").await?;
+        publish("second").await?;
+        yield_ms(100).await;
+        assert_eq!(vec!["second", "first"], received());
+        messages_1.write().expect("cannot drain").clear();
+
+        faults.publish(
+            topic.clone(),
+            PublishFault::Reject(RelayRpcError::<PublishError>::TooManyRequests.into()),
+        );
+        assert!(publish("rejected").await.is_err());
+        faults.clear(&topic);
+        publish("ok").await?;
+        yield_ms(100).await;
+        assert_eq!(vec!["ok"], received());
+
+        let rejected = Topic::generate();
+        faults.reject_subscribe(
+            rejected.clone(),
+            RelayRpcError::Handler(SubscriptionError::SubscriberLimitExceeded),
+        );
+        assert!(client_1.subscribe(rejected.clone()).await.is_err());
+        assert!(client_1
+            .batch_subscribe(vec![Topic::generate(), rejected])
+            .await
+            .is_err());
+
+        let state = relay.state();
+        assert_eq!(2, state.clients.len());
+        assert!(state
+            .clients
+            .iter()
+            .any(|c| c.topics == vec![topic.clone()]));
+
+        let subscribers = relay.subscribers(&topic);
+        assert_eq!(1, subscribers.len());
+        assert!(relay.force_close(subscribers[0]).await);
+        yield_ms(100).await;
+        assert!(client_1.subscribe(Topic::generate()).await.is_err());
+        relay.shutdown().await;
+        Ok(())
+    }
+
+    #[test]
+    fn parse_publish_fault() {
+        assert_eq!(Ok(PublishFault::Drop), "drop".parse());
+        assert_eq!(
+            Ok(PublishFault::Delay(Duration::from_millis(250))),
+            "delay:250".parse()
+        );
+        assert_eq!(Ok(PublishFault::Duplicate(2)), "duplicate:2".parse());
+        assert!(matches!(
+            "reject:3001".parse(),
+            Ok(PublishFault::Reject(ErrorData { code: 3001, .. }))
+        ));
+        assert!("delay".parse::<PublishFault>().is_err());
+        assert!("explode".parse::<PublishFault>().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn mock_relay_record_replay() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("mock-relay-{}.jsonl", Topic::generate()));
+        let relay = MockRelay::builder()
+            .ephemeral()
+            .record(&path)
+            .build()
+            .await?;
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let client_1 = Client::new(LogHandler::new(NoopHandler));
+        let client_2 = Client::new(LogHandler::new(NoopHandler));
+        client_1
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        client_2
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        let topic = Topic::generate();
+        client_1.subscribe(topic.clone()).await?;
+        yield_ms(100).await;
+        for m in ["one", "two"] {
+            client_2
+                .publish(
+                    topic.clone(),
+                    Arc::from(m),
+                    1108,
+                    Duration::from_secs(30),
+                    true,
+                )
+                .await?;
+            yield_ms(300).await;
+        }
+        relay.shutdown().await;
+
+        let records = TrafficRecorder::load(&path).await?;
+        let count = |kind| records.iter().filter(|r| r.kind == kind).count();
+        assert_eq!(TrafficKind::Subscribe, records[0].kind);
+        assert_eq!(2, count(TrafficKind::Publish));
+        assert_eq!(2, count(TrafficKind::Deliver));
+        let publish = records
+            .iter()
+            .find(|r| r.kind == TrafficKind::Publish)
+            .expect("publish was recorded");
+        assert_eq!(topic, publish.topic);
+        assert_eq!(1108, publish.tag);
+        assert_eq!(30, publish.ttl_secs);
+        assert!(publish.prompt);
+
+        // replay the recording to a fresh client
+        let relay = MockRelay::builder().ephemeral().build().await?;
+        let messages: MockMessages = Arc::new(std::sync::RwLock::new(VecDeque::new()));
+        let client_3 = Client::new(EchoHandler {
+            messages: messages.clone(),
+        });
+        client_3
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        yield_ms(50).await;
+        let client_id = relay.client_ids()[0];
+        let started = std::time::Instant::now();
+        assert_eq!(2, relay.replay(client_id, &path).await?);
+        // the second publish came 300ms after the first
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        yield_ms(100).await;
+        let received: Vec<String> = messages
+            .read()
+            .expect("could not unlock")
+            .iter()
+            .map(|m| m.message.to_string())
+            .collect();
+        assert_eq!(vec!["one", "two"], received);
+        relay.shutdown().await;
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    fn token(aud: &str, iat: chrono::DateTime<chrono::Utc>) -> crate::SerializedAuthToken {
+        let key =
+            reown_relay_rpc::auth::ed25519_dalek::SigningKey::generate(&mut rand::thread_rng());
+        crate::AuthToken::new(crate::AUTH_URL)
+            .aud(aud)
+            .iat(iat)
+            .ttl(Duration::from_hours(1))
+            .as_jwt(&key)
+            .expect("valid key")
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn mock_relay_validation() -> anyhow::Result<()> {
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let relay = MockRelay::builder()
+            .ephemeral()
+            .project_ids([project_id.clone()])
+            .max_message_size(16)
+            .build()
+            .await?;
+        let address = format!("ws://{}", relay.address());
+        let connect = |project_id: &ProjectId, auth| {
+            let opts = crate::ConnectionOptions::create(&address, project_id.clone(), auth);
+            async move { Client::new(NoopHandler).connect(&opts).await }
+        };
+        let now = chrono::Utc::now();
+        let expired = now - chrono::Duration::hours(3);
+        assert!(connect(&project_id, token(crate::RELAY_ADDRESS, expired))
+            .await
+            .is_err());
+        assert!(connect(&project_id, token("wss://elsewhere.com", now))
+            .await
+            .is_err());
+        let other = ProjectId::from("00000000000000000000000000000000");
+        assert!(connect(&other, token(crate::RELAY_ADDRESS, now))
+            .await
+            .is_err());
+        assert!(
+            connect(&ProjectId::from("nope"), token(crate::RELAY_ADDRESS, now))
+                .await
+                .is_err()
+        );
+        assert!(relay.client_ids().is_empty());
+
+        let client = Client::new(NoopHandler);
+        client.connect(&relay.connection_opts(&project_id)).await?;
+        let topic = Topic::generate();
+        let publish = |message: &'static str, ttl: u64| {
+            client.publish(
+                topic.clone(),
+                Arc::from(message),
+                0,
+                Duration::from_secs(ttl),
+                false,
+            )
+        };
+        assert!(publish("way too big for this relay", 30).await.is_err());
+        assert!(publish("short", 1).await.is_err());
+        assert!(publish("long", u64::from(u32::MAX)).await.is_err());
+        assert_eq!(0, relay.pending());
+        publish("fits", 30).await?;
+        assert_eq!(1, relay.pending());
+
+        let invalid = Topic::new(Arc::from("not-a-topic"));
+        assert!(client.subscribe(invalid.clone()).await.is_err());
+        assert!(client
+            .batch_subscribe(vec![Topic::generate(), invalid])
+            .await
+            .is_err());
+        relay.shutdown().await;
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn loopback_relay_validation() -> anyhow::Result<()> {
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let relay = LoopbackRelay::new();
+        let connect = |project_id: &ProjectId, auth| {
+            let mut opts = relay.connection_opts(project_id);
+            opts.set_auth(auth);
+            async move { Client::new(NoopHandler).connect(&opts).await }
+        };
+        let now = chrono::Utc::now();
+        let expired = now - chrono::Duration::hours(3);
+        assert!(connect(&project_id, token(crate::RELAY_ADDRESS, expired))
+            .await
+            .is_err());
+        assert!(connect(&project_id, token("wss://elsewhere.com", now))
+            .await
+            .is_err());
+        assert!(
+            connect(&ProjectId::from("nope"), token(crate::RELAY_ADDRESS, now))
+                .await
+                .is_err()
+        );
+
+        let closed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let client = Client::new(CloseHandler {
+            closed: closed.clone(),
+        });
+        client.connect(&relay.connection_opts(&project_id)).await?;
+        let invalid = Topic::new(Arc::from("not-a-topic"));
+        assert!(client.subscribe(invalid.clone()).await.is_err());
+        assert!(client.batch_subscribe(vec![invalid.clone()]).await.is_err());
+        assert!(client.unsubscribe(invalid).await.is_err());
+        let topic = Topic::generate();
+        let publish = |message: String, ttl: u64| {
+            client.publish(
+                topic.clone(),
+                Arc::from(message),
+                0,
+                Duration::from_secs(ttl),
+                false,
+            )
+        };
+        let too_big = "x".repeat(MAX_MESSAGE_SIZE + 1);
+        assert!(publish(too_big, 30).await.is_err());
+        assert!(publish(String::from("short"), MIN_TTL.as_secs() - 1)
+            .await
+            .is_err());
+        assert!(publish(String::from("long"), MAX_TTL.as_secs() + 1)
+            .await
+            .is_err());
+        assert_eq!(0, relay.pending());
+        publish(String::from("fits"), MIN_TTL.as_secs()).await?;
+        assert_eq!(1, relay.pending());
+
+        assert_eq!(1, relay.client_ids().len());
+        assert!(relay.close_with(relay.client_ids()[0], 1001, "going away"));
+        yield_ms(10).await;
+        assert!(closed.load(std::sync::atomic::Ordering::Relaxed));
+        assert!(relay.client_ids().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn mock_relay_origin_user_agent() -> anyhow::Result<()> {
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let relay = MockRelay::builder().ephemeral().build().await?;
+        let client = Client::new(NoopHandler);
+        client.connect(&relay.connection_opts(&project_id)).await?;
+        let anonymous = Client::new(NoopHandler);
+        anonymous
+            .connect(
+                &relay
+                    .connection_opts(&project_id)
+                    .with_origin(String::from("https://monedero.example"))
+                    .with_user_agent(None),
+            )
+            .await?;
+
+        // the relay registers a client after the upgrade has completed
+        let clients = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let clients = relay.state().clients;
+                if clients.len() == 2 {
+                    break clients;
+                }
+                yield_ms(10).await;
+            }
+        })
+        .await?;
+        let user_agent = crate::default_user_agent().to_string();
+        assert!(user_agent.starts_with("wc-2/rust-"));
+        assert!(clients
+            .iter()
+            .any(|c| c.origin.is_none() && c.user_agent.as_ref() == Some(&user_agent)));
+        assert!(clients.iter().any(|c| {
+            c.origin.as_deref() == Some("https://monedero.example") && c.user_agent.is_none()
+        }));
+        relay.shutdown().await;
+        Ok(())
+    }
+
+    #[test]
+    fn pending_expires() {
+        let pending = PendingMessages::default();
+        let topic = Topic::generate();
+        let publish = |ttl_secs| Publish {
+            topic: topic.clone(),
+            message: Arc::from(format!("{ttl_secs}")),
+            attestation: None,
+            ttl_secs,
+            tag: 0,
+            prompt: false,
+        };
+        pending.insert(publish(0));
+        pending.insert(publish(60));
+        assert_eq!(1, pending.len());
+        let taken = pending.take(&topic);
+        assert_eq!(1, taken.len());
+        assert_eq!(60, taken[0].ttl_secs);
+        assert_eq!(0, pending.len());
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+    async fn loopback_relay() -> anyhow::Result<()> {
+        let relay = LoopbackRelay::new();
+        let other = LoopbackRelay::new();
+        let messages_1: MockMessages = Arc::new(std::sync::RwLock::new(VecDeque::new()));
+        let messages_3: MockMessages = Arc::new(std::sync::RwLock::new(VecDeque::new()));
+        let client_1 = Client::new(EchoHandler {
+            messages: messages_1.clone(),
+        });
+        let client_2 = Client::new(LogHandler::new(NoopHandler));
+        let client_3 = Client::new(EchoHandler {
+            messages: messages_3.clone(),
+        });
+        let project_id = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+        let topic = Topic::generate();
+        client_1
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        client_2
+            .connect(&relay.connection_opts(&project_id))
+            .await?;
+        client_3
+            .connect(&other.connection_opts(&project_id))
+            .await?;
+        client_1.subscribe(topic.clone()).await?;
+        client_3.subscribe(topic.clone()).await?;
+        client_2
+            .publish(
+                topic.clone(),
+                Arc::from("reown the world"),
+                0,
+                Duration::from_secs(30),
+                false,
+            )
+            .await?;
+        yield_ms(10).await;
+        assert_eq!(1, messages_1.read().expect("could not unlock").len());
+        // different instance never sees the message
+        assert_eq!(0, messages_3.read().expect("could not unlock").len());
+
+        // unsubscribed publishes are held until someone subscribes
+        client_1.unsubscribe(topic.clone()).await?;
+        client_2
+            .publish(
+                topic.clone(),
+                Arc::from("reown the world"),
+                0,
+                Duration::from_secs(30),
+                false,
+            )
+            .await?;
+        yield_ms(10).await;
+        assert_eq!(1, relay.pending());
+        assert_eq!(1, messages_1.read().expect("could not unlock").len());
+        client_1
+            .batch_subscribe(vec![topic.clone(), Topic::generate()])
+            .await?;
+        yield_ms(10).await;
+        assert_eq!(0, relay.pending());
+        assert_eq!(2, messages_1.read().expect("could not unlock").len());
+
+        client_1.disconnect().await?;
+        assert!(client_1.subscribe(topic).await.is_err());
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
     async fn real_relay() -> anyhow::Result<()> {
         let run = std::env::var("REAL_RELAY").ok();
@@ -0,0 +1,109 @@
+use {
+    super::{
+        validate::Validation,
+        MockRelay,
+        PendingMessages,
+        TrafficRecorder,
+        WsPublishedMessage,
+    },
+    crate::{ProjectId, MOCK_RELAY_ADDRESS},
+    std::{collections::HashSet, net::SocketAddr, path::PathBuf, sync::Arc},
+    tokio::net::TcpListener,
+};
+
+/// Configure and start a [`MockRelay`].
+///
+/// Without an address the relay listens on `127.0.0.1:4001`. Use
+/// [`MockRelayBuilder::ephemeral`] to let the OS pick a free port, so several
+/// relays can run side by side.
+///
+/// Connections must present a JWT for the relay audience and a well formed
+/// project ID, like they would against the real relay.
+pub struct MockRelayBuilder {
+    address: String,
+    record: Option<PathBuf>,
+    validation: Validation,
+}
+
+impl Default for MockRelayBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockRelayBuilder {
+    pub fn new() -> Self {
+        Self {
+            address: String::from(MOCK_RELAY_ADDRESS),
+            record: None,
+            validation: Validation::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn address(mut self, address: SocketAddr) -> Self {
+        self.address = address.to_string();
+        self
+    }
+
+    #[must_use]
+    pub fn port(mut self, port: u16) -> Self {
+        self.address = format!("127.0.0.1:{port}");
+        self
+    }
+
+    /// Bind to any free port on localhost.
+    #[must_use]
+    pub fn ephemeral(self) -> Self {
+        self.port(0)
+    }
+
+    /// Append all traffic to a JSONL file at `path`.
+    /// See [`super::TrafficRecorder`]
+    #[must_use]
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record = Some(path.into());
+        self
+    }
+
+    /// Accept JWTs issued for `audience`, in addition to the default relay
+    /// address and the instance's own `ws://` address.
+    #[must_use]
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.validation.audience.insert(audience.into());
+        self
+    }
+
+    /// Only accept connections for these projects.
+    #[must_use]
+    pub fn project_ids(mut self, project_ids: impl IntoIterator<Item = ProjectId>) -> Self {
+        self.validation.project_ids =
+            Some(Arc::new(project_ids.into_iter().collect::<HashSet<_>>()));
+        self
+    }
+
+    /// Reject publishes with a larger message. Defaults to
+    /// [`super::MAX_MESSAGE_SIZE`].
+    #[must_use]
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.validation.max_message_size = size;
+        self
+    }
+
+    pub async fn build(&self) -> crate::Result<MockRelay> {
+        let recorder = match &self.record {
+            Some(path) => Some(TrafficRecorder::create(path).await?),
+            None => None,
+        };
+        let listener = TcpListener::bind(&self.address).await?;
+        let (tx, _rx) = tokio::sync::broadcast::channel::<WsPublishedMessage>(100);
+        MockRelay::bind(
+            listener,
+            PendingMessages::default(),
+            tx,
+            recorder,
+            self.validation.clone(),
+        )
+        .await
+    }
+}
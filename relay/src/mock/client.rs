@@ -1,7 +1,18 @@
 use {
-    super::{MockRelay, PendingMessages, WsPublishedMessage, WsSender},
+    super::{
+        MockFaults,
+        MockRelay,
+        PendingMessages,
+        PublishFault,
+        TrafficKind,
+        TrafficRecord,
+        TrafficRecorder,
+        WsPublishedMessage,
+        WsSender,
+    },
     crate::Topic,
-    dashmap::DashSet,
+    dashmap::{DashMap, DashSet},
+    futures_util::SinkExt,
     reown_relay_client::MessageIdGenerator,
     reown_relay_rpc::{
         domain::{MessageId, SubscriptionId},
@@ -14,17 +25,31 @@ use {
         time::Duration,
     },
     tokio::sync::broadcast::Receiver,
+    tokio_tungstenite::tungstenite::{protocol::CloseFrame, Message},
     tracing::{debug, warn, Level},
 };
 
+/// What the client sent in its websocket upgrade request.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Handshake {
+    pub(super) origin: Option<String>,
+    pub(super) user_agent: Option<String>,
+    pub(super) audience: Option<String>,
+}
+
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct WsClient {
     pub id: u16,
+    pub(super) handshake: Handshake,
     topics: Arc<DashSet<Topic>>,
     ws_sender: WsSender,
     generator: MessageIdGenerator,
     pending: PendingMessages,
+    faults: MockFaults,
+    /// Publishes held back by [`PublishFault::Reorder`]
+    held: Arc<DashMap<Topic, Publish>>,
+    recorder: Option<TrafficRecorder>,
     // sent: SentMessages,
 }
 
@@ -58,19 +83,61 @@ impl WsClient {
         format!("[wsclient-{}]({})", self.id, self.topics.len())
     }
 
-    pub fn new(relay: &MockRelay, id: u16, ws_sender: WsSender) -> Self {
+    pub(super) fn new(
+        relay: &MockRelay,
+        id: u16,
+        ws_sender: WsSender,
+        handshake: Handshake,
+    ) -> Self {
         let me = Self {
             id,
+            handshake,
             ws_sender,
             topics: Arc::new(DashSet::new()),
             generator: relay.generator.clone(),
             pending: relay.pending.clone(),
+            faults: relay.faults.clone(),
+            held: Arc::new(DashMap::new()),
+            recorder: relay.recorder.clone(),
         };
         let listener = me.clone();
         tokio::spawn(listener.handle_message(relay.tx.subscribe()));
         me
     }
 
+    /// Closes the websocket, as if the relay dropped the connection.
+    pub async fn close(&self) {
+        if let Err(e) = self.ws_sender.lock().await.close().await {
+            debug!("{self} failed to close {e}");
+        }
+    }
+
+    /// Closes the websocket with a close frame, as the relay does when it
+    /// rejects a client.
+    pub async fn close_with(&self, code: u16, reason: &str) {
+        let frame = CloseFrame {
+            code: code.into(),
+            reason: String::from(reason).into(),
+        };
+        if let Err(e) = self
+            .ws_sender
+            .lock()
+            .await
+            .send(Message::Close(Some(frame)))
+            .await
+        {
+            debug!("{self} failed to close {e}");
+        }
+    }
+
+    pub fn topics(&self) -> Vec<Topic> {
+        self.topics.iter().map(|t| t.clone()).collect()
+    }
+
+    pub fn is_subscribed(&self, topic: &Topic) -> bool {
+        self.topics.contains(topic)
+    }
+
     async fn handle_message(self, mut rx: Receiver<WsPublishedMessage>) {
         while let Ok(published_message) = rx.recv().await {
             if published_message.close {
@@ -115,32 +182,68 @@ impl WsClient {
         };
     }
 
+    fn subscription_payload(&self, p: &Publish) -> Payload {
+        let forward_id = self.generator.next();
+        let now = chrono::Utc::now().timestamp();
+        let subscription_id = SubscriptionId::from(p.topic.as_ref());
+        Payload::Request(p.as_subscription_request(forward_id, subscription_id, now))
+    }
+
     fn send_message(&self, messages: Vec<Publish>) {
+        let mut outgoing: Vec<(Publish, Duration)> = Vec::with_capacity(messages.len());
         for p in messages {
-            let forward_id = self.generator.next();
-            let now = chrono::Utc::now().timestamp();
-            let subscription_id = SubscriptionId::from(p.topic.as_ref());
-            let sub_req = p.as_subscription_request(forward_id, subscription_id, now);
-            let forward_payload = Payload::Request(sub_req);
-            tokio::spawn(MockRelay::forward(
-                forward_payload,
-                self.ws_sender.clone(),
-                Duration::from_millis(50),
-            ));
+            match self.faults.publish_fault(&p.topic) {
+                None => outgoing.push((p, Duration::ZERO)),
+                Some(PublishFault::Drop | PublishFault::Reject(_)) => {
+                    debug!("{self} dropping message on {}", p.topic);
+                }
+                Some(PublishFault::Delay(delay)) => {
+                    outgoing.push((p.clone(), delay));
+                }
+                Some(PublishFault::Duplicate(n)) => {
+                    for _ in 0..=n {
+                        outgoing.push((p.clone(), Duration::ZERO));
+                    }
+                }
+                Some(PublishFault::Reorder) => {
+                    if let Some((_, held)) = self.held.remove(&p.topic) {
+                        outgoing.push((p.clone(), Duration::ZERO));
+                        outgoing.push((held, Duration::ZERO));
+                    } else {
+                        debug!("{self} holding back message on {}", p.topic);
+                        self.held.insert(p.topic.clone(), p);
+                    }
+                }
+            }
+        }
+        if outgoing.is_empty() {
+            return;
         }
+        let me = self.clone();
+        tokio::spawn(async move {
+            for (p, delay) in outgoing {
+                tokio::time::sleep(delay).await;
+                me.deliver(&p).await;
+            }
+        });
+    }
+
+    /// Sends `p` to this client as a subscription message.
+    pub async fn deliver(&self, p: &Publish) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(TrafficRecord::publish(TrafficKind::Deliver, self.id, p));
+        }
+        MockRelay::forward(
+            self.subscription_payload(p),
+            self.ws_sender.clone(),
+            Duration::ZERO,
+        )
+        .await;
     }
 
     fn check_pending(&self, topic: &Topic) {
-        let to_send: Vec<Publish> = self
-            .pending
-            .iter()
-            .filter(|m| m.topic == *topic)
-            .map(|m| m.clone())
-            .collect();
+        let to_send = self.pending.take(topic);
         debug!("found {} to send", to_send.len());
-        for p in &to_send {
-            self.pending.remove(p);
-        }
         self.send_message(to_send);
     }
 
@@ -0,0 +1,121 @@
+use {
+    crate::Topic,
+    dashmap::DashMap,
+    reown_relay_rpc::rpc::ErrorData,
+    std::{
+        str::FromStr,
+        sync::{Arc, PoisonError, RwLock},
+        time::Duration,
+    },
+};
+
+/// Misbehaviour applied to publishes on a topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublishFault {
+    /// Acknowledge the publish but never deliver it.
+    Drop,
+    /// Deliver the message after the given delay.
+    Delay(Duration),
+    /// Deliver the message this many extra times.
+    Duplicate(usize),
+    /// Hold the message back and deliver it after the next publish on the
+    /// same topic.
+    Reorder,
+    /// Answer the publish with a relay error instead of an ack.
+    Reject(ErrorData),
+}
+
+/// Parses `drop`, `delay:<ms>`, `duplicate:<n>`, `reorder` or
+/// `reject:<code>`.
+impl FromStr for PublishFault {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = s.split_once(':').unwrap_or((s, ""));
+        let number = |what: &str| -> Result<u64, String> {
+            arg.parse::<u64>()
+                .map_err(|_| format!("{name} needs {what}, got '{arg}'"))
+        };
+        match name {
+            "drop" => Ok(Self::Drop),
+            "reorder" => Ok(Self::Reorder),
+            "delay" => Ok(Self::Delay(Duration::from_millis(number("milliseconds")?))),
+            "duplicate" => Ok(Self::Duplicate(
+                usize::try_from(number("a count")?).map_err(|e| e.to_string())?,
+            )),
+            "reject" => {
+                let code = arg
+                    .parse::<i32>()
+                    .map_err(|_| format!("reject needs an error code, got '{arg}'"))?;
+                Ok(Self::Reject(ErrorData {
+                    code,
+                    message: String::from("rejected by mock relay"),
+                    data: None,
+                }))
+            }
+            _ => Err(format!("unknown fault '{s}'")),
+        }
+    }
+}
+
+/// Runtime controllable fault layer of a [`super::MockRelay`].
+///
+/// Clones share state, so a test can keep a handle and change the relay's
+/// behaviour while clients are connected.
+#[derive(Debug, Clone, Default)]
+pub struct MockFaults {
+    publish: Arc<DashMap<Topic, PublishFault>>,
+    subscribe: Arc<DashMap<Topic, ErrorData>>,
+    /// Applied to topics without their own publish fault
+    default: Arc<RwLock<Option<PublishFault>>>,
+}
+
+impl MockFaults {
+    /// Apply `fault` to every publish on `topic`, replacing any previous fault.
+    pub fn publish(&self, topic: Topic, fault: PublishFault) {
+        self.publish.insert(topic, fault);
+    }
+
+    /// Apply `fault` to publishes on every topic that has no fault of its
+    /// own. `None` removes it.
+    pub fn publish_default(&self, fault: Option<PublishFault>) {
+        *self.default.write().unwrap_or_else(PoisonError::into_inner) = fault;
+    }
+
+    /// Reject subscriptions to `topic` with `error`.
+    pub fn reject_subscribe(&self, topic: Topic, error: impl Into<ErrorData>) {
+        self.subscribe.insert(topic, error.into());
+    }
+
+    /// Remove all faults for `topic`.
+    pub fn clear(&self, topic: &Topic) {
+        self.publish.remove(topic);
+        self.subscribe.remove(topic);
+    }
+
+    pub fn clear_all(&self) {
+        self.publish.clear();
+        self.subscribe.clear();
+        self.publish_default(None);
+    }
+
+    pub(super) fn publish_fault(&self, topic: &Topic) -> Option<PublishFault> {
+        self.publish.get(topic).map(|f| f.clone()).or_else(|| {
+            self.default
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        })
+    }
+
+    pub(super) fn publish_rejection(&self, topic: &Topic) -> Option<ErrorData> {
+        match self.publish_fault(topic) {
+            Some(PublishFault::Reject(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub(super) fn subscribe_rejection(&self, topic: &Topic) -> Option<ErrorData> {
+        self.subscribe.get(topic).map(|e| e.clone())
+    }
+}
@@ -0,0 +1,332 @@
+use {
+    super::{validate::Validation, PendingMessages},
+    crate::{
+        CloseFrame,
+        ConnectionHandler,
+        ConnectionOptions,
+        Message,
+        ProjectId,
+        Topic,
+        AUTH_URL,
+    },
+    dashmap::{DashMap, DashSet},
+    reown_relay_client::{Authorization, MessageIdGenerator},
+    reown_relay_rpc::{
+        domain::SubscriptionId,
+        rpc::{ErrorData, Params, Publish, Request, Subscribe, Unsubscribe},
+    },
+    std::{
+        fmt::{Debug, Display},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+            Mutex,
+            PoisonError,
+        },
+        time::Duration,
+    },
+    tokio::sync::mpsc,
+    tracing::debug,
+};
+
+pub type SharedHandler = Arc<Mutex<dyn ConnectionHandler>>;
+
+/// Websocket close code of a normal closure.
+const CLOSE_NORMAL: u16 = 1000;
+
+/// Address in the [`ConnectionOptions`] of a [`LoopbackRelay`], tokens minted
+/// for it are accepted as well as those for [`crate::RELAY_ADDRESS`].
+const LOOPBACK_ADDRESS: &str = "loopback://";
+
+/// In-process relay that routes publishes between [`crate::Client`]s without
+/// any sockets.
+///
+/// Every instance is isolated, so tests can create as many as they need and
+/// run in parallel. Subscribe and pending semantics follow the websocket
+/// [`super::MockRelay`]: a publish is delivered to every other client
+/// subscribed to the topic, otherwise it is held until someone subscribes.
+/// Connections and requests go through the same project ID, JWT, topic, TTL
+/// and size checks as the websocket relay.
+#[derive(Clone)]
+pub struct LoopbackRelay {
+    clients: Arc<DashMap<u64, LoopbackClient>>,
+    pending: PendingMessages,
+    validation: Validation,
+    generator: MessageIdGenerator,
+    next_id: Arc<AtomicU64>,
+    auth_tokens: Arc<Mutex<Vec<String>>>,
+}
+
+impl Default for LoopbackRelay {
+    fn default() -> Self {
+        let mut validation = Validation::default();
+        validation.audience.insert(String::from(LOOPBACK_ADDRESS));
+        Self {
+            clients: Arc::default(),
+            pending: PendingMessages::default(),
+            validation,
+            generator: MessageIdGenerator::default(),
+            next_id: Arc::default(),
+            auth_tokens: Arc::default(),
+        }
+    }
+}
+
+impl Debug for LoopbackRelay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[loopback-relay]({}/{})",
+            self.clients.len(),
+            self.pending.len()
+        )
+    }
+}
+
+enum Delivery {
+    Message(Message),
+    Closed(CloseFrame<'static>),
+}
+
+#[derive(Clone)]
+struct LoopbackClient {
+    id: u64,
+    topics: Arc<DashSet<Topic>>,
+    tx: mpsc::UnboundedSender<Delivery>,
+}
+
+impl Display for LoopbackClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[loopback-{}]({})", self.id, self.topics.len())
+    }
+}
+
+/// A client's registration on a [`LoopbackRelay`].
+#[derive(Clone)]
+pub struct LoopbackConnection {
+    id: u64,
+    relay: LoopbackRelay,
+}
+
+impl LoopbackRelay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connection options that route a [`crate::Client`] to this instance.
+    pub fn connection_opts(&self, project_id: &ProjectId) -> ConnectionOptions {
+        let mut opts = ConnectionOptions::create(
+            LOOPBACK_ADDRESS,
+            project_id.clone(),
+            crate::auth_token(AUTH_URL),
+        );
+        opts.loopback = Some(self.clone());
+        opts
+    }
+
+    /// Number of publishes waiting for a subscriber.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// IDs of all connected clients.
+    pub fn client_ids(&self) -> Vec<u64> {
+        self.clients.iter().map(|c| c.id).collect()
+    }
+
+    /// Drop a client with `code` and `reason`, its handler gets them as the
+    /// close frame. Returns false if the client is not connected.
+    pub fn close_with(&self, client_id: u64, code: u16, reason: &str) -> bool {
+        let Some((_, client)) = self.clients.remove(&client_id) else {
+            return false;
+        };
+        let frame = CloseFrame {
+            code,
+            reason: String::from(reason).into(),
+        };
+        if client.tx.send(Delivery::Closed(frame)).is_err() {
+            debug!("{client} has gone away");
+        }
+        true
+    }
+
+    /// Auth tokens of the accepted connections, oldest first.
+    pub fn auth_tokens(&self) -> Vec<String> {
+        self.auth_tokens
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Fails with the relay's error when the project ID or JWT of `opts` is
+    /// rejected.
+    pub(crate) fn connect(
+        &self,
+        handler: SharedHandler,
+        opts: &ConnectionOptions,
+    ) -> Result<LoopbackConnection, ErrorData> {
+        let project_id = format!("projectId={}", opts.project_id);
+        let (query, header) = match &opts.auth {
+            Some(Authorization::Query(token)) => (format!("{project_id}&auth={token}"), None),
+            Some(Authorization::Header(token)) => (project_id, Some(format!("Bearer {token}"))),
+            None => (project_id, None),
+        };
+        self.validation
+            .check_handshake(Some(&query), header.as_deref())?;
+        if let Some(Authorization::Query(token) | Authorization::Header(token)) = &opts.auth {
+            self.auth_tokens
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(token.to_string());
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::unbounded_channel::<Delivery>();
+        let client = LoopbackClient {
+            id,
+            topics: Arc::new(DashSet::new()),
+            tx,
+        };
+        debug!("created new loopback client {client}");
+        self.clients.insert(id, client);
+        tokio::spawn(deliver(handler, rx));
+        Ok(LoopbackConnection {
+            id,
+            relay: self.clone(),
+        })
+    }
+
+    fn send_message(&self, client: &LoopbackClient, p: &Publish) {
+        let now = chrono::Utc::now();
+        let message = Message {
+            id: self.generator.next(),
+            subscription_id: SubscriptionId::from(p.topic.as_ref()),
+            topic: p.topic.clone(),
+            message: p.message.clone(),
+            tag: p.tag,
+            published_at: now,
+            received_at: now,
+        };
+        if client.tx.send(Delivery::Message(message)).is_err() {
+            debug!("{client} has gone away");
+        }
+    }
+
+    fn check_pending(&self, client: &LoopbackClient, topic: &Topic) {
+        let to_send = self.pending.take(topic);
+        debug!("found {} to send", to_send.len());
+        for p in &to_send {
+            self.send_message(client, p);
+        }
+    }
+}
+
+async fn deliver(handler: SharedHandler, mut rx: mpsc::UnboundedReceiver<Delivery>) {
+    while let Some(delivery) = rx.recv().await {
+        match delivery {
+            Delivery::Message(message) => handler
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .message_received(message),
+            Delivery::Closed(frame) => {
+                handler
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .disconnected(Some(frame));
+                return;
+            }
+        }
+    }
+}
+
+impl LoopbackConnection {
+    fn client(&self) -> Option<LoopbackClient> {
+        self.relay.clients.get(&self.id).map(|c| c.clone())
+    }
+
+    /// Returns `None` when this connection has been closed, otherwise the
+    /// relay's error if the publish is rejected.
+    pub fn publish(
+        &self,
+        topic: Topic,
+        message: Arc<str>,
+        tag: u32,
+        ttl: Duration,
+        prompt: bool,
+    ) -> Option<Result<(), ErrorData>> {
+        let me = self.client()?;
+        let p = Publish {
+            topic,
+            message,
+            attestation: None,
+            ttl_secs: u32::try_from(ttl.as_secs()).unwrap_or(u32::MAX),
+            tag,
+            prompt,
+        };
+        if let Err(e) = self.check(Params::Publish(p.clone())) {
+            debug!("{me} rejecting publish: {}", e.message);
+            return Some(Err(e));
+        }
+        let subscribers: Vec<LoopbackClient> = self
+            .relay
+            .clients
+            .iter()
+            .filter(|c| c.id != me.id && c.topics.contains(&p.topic))
+            .map(|c| c.clone())
+            .collect();
+        if subscribers.is_empty() {
+            debug!("{me} no one is subscribed to {}", p.topic);
+            self.relay.pending.insert(p);
+            return Some(Ok(()));
+        }
+        for s in &subscribers {
+            self.relay.send_message(s, &p);
+        }
+        Some(Ok(()))
+    }
+
+    /// Returns `None` when this connection has been closed, otherwise the
+    /// relay's error if the subscribe is rejected.
+    pub fn subscribe(&self, topic: &Topic) -> Option<Result<SubscriptionId, ErrorData>> {
+        let me = self.client()?;
+        if let Err(e) = self.check(Params::Subscribe(Subscribe {
+            topic: topic.clone(),
+        })) {
+            debug!("{me} rejecting subscribe: {}", e.message);
+            return Some(Err(e));
+        }
+        me.topics.insert(topic.clone());
+        self.relay.check_pending(&me, topic);
+        Some(Ok(SubscriptionId::from(topic.as_ref())))
+    }
+
+    /// Returns `None` when this connection has been closed, otherwise the
+    /// relay's error if the unsubscribe is rejected.
+    pub fn unsubscribe(&self, topic: &Topic) -> Option<Result<(), ErrorData>> {
+        let me = self.client()?;
+        if let Err(e) = self.check(Params::Unsubscribe(Unsubscribe {
+            topic: topic.clone(),
+        })) {
+            debug!("{me} rejecting unsubscribe: {}", e.message);
+            return Some(Err(e));
+        }
+        me.topics.remove(topic);
+        Some(Ok(()))
+    }
+
+    /// The relay drops this connection, its handler gets a normal close
+    /// frame.
+    pub fn disconnect(&self) {
+        self.relay.close_with(self.id, CLOSE_NORMAL, "");
+    }
+
+    /// Closed by the client, which like a websocket client does not tell its
+    /// own handler.
+    pub(crate) fn close(&self) {
+        self.relay.clients.remove(&self.id);
+    }
+
+    fn check(&self, params: Params) -> Result<(), ErrorData> {
+        let req = Request::new(self.relay.generator.next(), params);
+        self.relay.validation.check_request(&req)
+    }
+}
@@ -0,0 +1,197 @@
+use {
+    crate::Topic,
+    reown_relay_rpc::rpc::Publish,
+    serde::{Deserialize, Serialize},
+    std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::{
+        fs::{File, OpenOptions},
+        io::AsyncWriteExt,
+        sync::{mpsc, oneshot},
+    },
+    tracing::warn,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrafficKind {
+    /// A client published a message.
+    Publish,
+    /// The relay delivered a message to a subscriber.
+    Deliver,
+    Subscribe,
+    Unsubscribe,
+}
+
+/// One line of a traffic recording.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficRecord {
+    pub kind: TrafficKind,
+    /// Port of the client that sent or received the message
+    pub client_id: u16,
+    pub topic: Topic,
+    /// The encrypted envelope, for publishes and deliveries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Arc<str>>,
+    #[serde(default)]
+    pub tag: u32,
+    #[serde(default)]
+    pub ttl_secs: u32,
+    #[serde(default)]
+    pub prompt: bool,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+    /// Milliseconds since the recording started, used to keep the original
+    /// timing on replay
+    #[serde(default)]
+    pub offset_ms: u64,
+}
+
+impl TrafficRecord {
+    pub(super) fn topic(kind: TrafficKind, client_id: u16, topic: Topic) -> Self {
+        Self {
+            kind,
+            client_id,
+            topic,
+            message: None,
+            tag: 0,
+            ttl_secs: 0,
+            prompt: false,
+            recorded_at: chrono::Utc::now(),
+            offset_ms: 0,
+        }
+    }
+
+    pub(super) fn publish(kind: TrafficKind, client_id: u16, p: &Publish) -> Self {
+        Self {
+            kind,
+            client_id,
+            topic: p.topic.clone(),
+            message: Some(p.message.clone()),
+            tag: p.tag,
+            ttl_secs: p.ttl_secs,
+            prompt: p.prompt,
+            recorded_at: chrono::Utc::now(),
+            offset_ms: 0,
+        }
+    }
+
+    /// Time since the recording started.
+    pub const fn offset(&self) -> Duration {
+        Duration::from_millis(self.offset_ms)
+    }
+
+    /// The publish this record was made from, if it carries a message.
+    pub fn as_publish(&self) -> Option<Publish> {
+        let message = self.message.clone()?;
+        Some(Publish {
+            topic: self.topic.clone(),
+            message,
+            attestation: None,
+            ttl_secs: self.ttl_secs,
+            tag: self.tag,
+            prompt: self.prompt,
+        })
+    }
+}
+
+/// Appends the traffic of a [`super::MockRelay`] to a JSONL file, one
+/// [`TrafficRecord`] per line.
+///
+/// Records are written by a background task so the server never blocks on
+/// the file, see [`Self::flush`].
+#[derive(Clone)]
+pub struct TrafficRecorder {
+    path: PathBuf,
+    started: Instant,
+    tx: mpsc::UnboundedSender<Command>,
+}
+
+enum Command {
+    Line(String),
+    Flush(oneshot::Sender<()>),
+}
+
+impl std::fmt::Debug for TrafficRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[recorder {}]", self.path.display())
+    }
+}
+
+impl TrafficRecorder {
+    /// Opens `path` for appending, creating it if needed.
+    pub async fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_records(path.clone(), file, rx));
+        Ok(Self {
+            path,
+            started: Instant::now(),
+            tx,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads every record of a recording.
+    pub async fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<TrafficRecord>> {
+        let content = tokio::fs::read_to_string(path).await?;
+        let mut records = Vec::new();
+        for line in content.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push(serde_json::from_str(line)?);
+        }
+        Ok(records)
+    }
+
+    /// Waits until every record so far is written to the file.
+    pub async fn flush(&self) {
+        let (tx, rx) = oneshot::channel();
+        if self.tx.send(Command::Flush(tx)).is_ok() {
+            let _ = rx.await;
+        }
+    }
+
+    pub(super) fn record(&self, mut record: TrafficRecord) {
+        record.offset_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let mut line = match serde_json::to_string(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("failed to serialize traffic record {e}");
+                return;
+            }
+        };
+        line.push('\n');
+        let _ = self.tx.send(Command::Line(line));
+    }
+}
+
+async fn write_records(path: PathBuf, mut file: File, mut rx: mpsc::UnboundedReceiver<Command>) {
+    while let Some(command) = rx.recv().await {
+        match command {
+            Command::Line(line) => {
+                if let Err(e) = file.write_all(line.as_bytes()).await {
+                    warn!("failed to write to {} {e}", path.display());
+                }
+            }
+            Command::Flush(done) => {
+                if let Err(e) = file.flush().await {
+                    warn!("failed to flush {} {e}", path.display());
+                }
+                let _ = done.send(());
+            }
+        }
+    }
+}
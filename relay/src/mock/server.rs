@@ -1,23 +1,56 @@
 use {
-    super::{client::WsClient, PendingMessages, WsPublishedMessage},
-    crate::MOCK_RELAY_ADDRESS,
-    dashmap::{DashMap, DashSet},
+    super::{
+        client::{Handshake, WsClient},
+        validate::Validation,
+        MockClientState,
+        MockFaults,
+        MockPendingState,
+        MockRelayBuilder,
+        MockRelayState,
+        PendingMessages,
+        TrafficKind,
+        TrafficRecord,
+        TrafficRecorder,
+        WsPublishedMessage,
+    },
+    crate::{ClientError, ConnectionOptions, ProjectId, AUTH_URL},
+    dashmap::DashMap,
     futures_util::{stream::SplitSink, SinkExt, StreamExt},
     reown_relay_client::MessageIdGenerator,
     reown_relay_rpc::{
         domain::MessageId,
-        rpc::{Payload, Response, SuccessfulResponse},
+        rpc::{ErrorData, ErrorResponse, Params, Payload, Response, SuccessfulResponse},
     },
     serde::Serialize,
-    std::{fmt::Debug, net::SocketAddr, sync::Arc, time::Duration},
+    std::{fmt::Debug, net::SocketAddr, path::Path, sync::Arc, time::Duration},
     tokio::{
         net::{TcpListener, TcpStream},
-        sync::Mutex,
+        sync::{watch, Mutex},
+        task::{JoinHandle, JoinSet},
+    },
+    tokio_tungstenite::{
+        accept_hdr_async,
+        tungstenite::{
+            handshake::server::{
+                ErrorResponse as HandshakeError,
+                Request as HandshakeRequest,
+                Response as HandshakeResponse,
+            },
+            http::{
+                header::{AUTHORIZATION, ORIGIN},
+                StatusCode,
+            },
+            Message,
+        },
+        WebSocketStream,
     },
-    tokio_tungstenite::{accept_async, tungstenite::Message, WebSocketStream},
-    tracing::{debug, error, info, Level},
+    tracing::{debug, error, info, warn, Level},
 };
 
+async fn stopped(shutdown: &mut watch::Receiver<bool>) {
+    let _ = shutdown.wait_for(|stop| *stop).await;
+}
+
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct MockRelay {
@@ -25,61 +58,265 @@ pub struct MockRelay {
     pub(super) pending: PendingMessages,
     pub(super) tx: tokio::sync::broadcast::Sender<WsPublishedMessage>,
     pub(super) generator: MessageIdGenerator,
+    pub(super) faults: MockFaults,
+    pub(super) recorder: Option<TrafficRecorder>,
+    validation: Validation,
+    addr: SocketAddr,
+    shutdown: Arc<watch::Sender<bool>>,
+    server: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl Debug for MockRelay {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[mock-relay]({})", self.pending.len())
+        write!(f, "[mock-relay {}]({})", self.addr, self.pending.len())
     }
 }
 
 impl MockRelay {
-    /// Starts the mock relay server and returns an instance of `MockRelay`.
+    /// Starts the mock relay server on the default address `127.0.0.1:4001`.
+    ///
+    /// Use [`MockRelay::builder`] to pick another port or run more than one
+    /// instance.
     pub async fn start() -> crate::Result<Self> {
-        info!("Starting mock relay server on {MOCK_RELAY_ADDRESS}");
-        let listener = TcpListener::bind(MOCK_RELAY_ADDRESS).await?;
-        let (tx, _rx) = tokio::sync::broadcast::channel::<WsPublishedMessage>(100);
+        Self::builder().build().await
+    }
+
+    pub fn builder() -> MockRelayBuilder {
+        MockRelayBuilder::new()
+    }
+
+    pub(super) async fn bind(
+        listener: TcpListener,
+        pending: PendingMessages,
+        tx: tokio::sync::broadcast::Sender<WsPublishedMessage>,
+        recorder: Option<TrafficRecorder>,
+        mut validation: Validation,
+    ) -> crate::Result<Self> {
+        let addr = listener.local_addr()?;
+        // tokens minted for this instance's own address are accepted too
+        validation.audience.insert(format!("ws://{addr}"));
+        info!("Starting mock relay server on {addr}");
+        let (shutdown, _) = watch::channel(false);
         let me = Self {
             clients: Arc::new(DashMap::new()),
-            pending: Arc::new(DashSet::new()),
+            pending,
             tx,
             generator: MessageIdGenerator::new(),
+            faults: MockFaults::default(),
+            recorder,
+            validation,
+            addr,
+            shutdown: Arc::new(shutdown),
+            server: Arc::new(Mutex::new(None)),
         };
-
-        tokio::spawn(Self::run(me.clone(), listener));
+        let server = tokio::spawn(Self::run(me.clone(), listener));
+        *me.server.lock().await = Some(server);
         Ok(me)
     }
 
+    /// The address the server is listening on.
+    pub const fn address(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Connection options pointing at this instance.
+    pub fn connection_opts(&self, project_id: &ProjectId) -> ConnectionOptions {
+        ConnectionOptions::create(
+            &format!("ws://{}", self.addr),
+            project_id.clone(),
+            crate::auth_token(AUTH_URL),
+        )
+    }
+
+    /// Handle to change the relay's fault injection at runtime.
+    pub fn faults(&self) -> MockFaults {
+        self.faults.clone()
+    }
+
+    /// Connected clients, their subscriptions and the undelivered publishes.
+    pub fn state(&self) -> MockRelayState {
+        let mut clients: Vec<MockClientState> = self
+            .clients
+            .iter()
+            .map(|c| MockClientState {
+                id: c.id,
+                topics: c.topics(),
+                origin: c.handshake.origin.clone(),
+                user_agent: c.handshake.user_agent.clone(),
+                audience: c.handshake.audience.clone(),
+            })
+            .collect();
+        clients.sort_by_key(|c| c.id);
+        let pending = self
+            .pending
+            .messages()
+            .into_iter()
+            .map(|p| MockPendingState {
+                topic: p.topic.clone(),
+                tag: p.tag,
+                ttl_secs: p.ttl_secs,
+                prompt: p.prompt,
+            })
+            .collect();
+        MockRelayState { clients, pending }
+    }
+
+    /// Number of publishes waiting for a subscriber.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// IDs of all connected clients.
+    pub fn client_ids(&self) -> Vec<u16> {
+        self.clients.iter().map(|c| c.id).collect()
+    }
+
+    /// IDs of the connected clients subscribed to `topic`.
+    pub fn subscribers(&self, topic: &crate::Topic) -> Vec<u16> {
+        self.clients
+            .iter()
+            .filter(|c| c.is_subscribed(topic))
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// Force close the websocket of a client. Returns false if the client is
+    /// not connected.
+    pub async fn force_close(&self, client_id: u16) -> bool {
+        let client = self.clients.get(&client_id).map(|c| c.clone());
+        match client {
+            Some(client) => {
+                client.close().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close the websocket of a client with `code` and `reason`, e.g.
+    /// [`crate::CLOSE_UNAUTHORIZED`]. Returns false if the client is not
+    /// connected.
+    pub async fn close_with(&self, client_id: u16, code: u16, reason: &str) -> bool {
+        let client = self.clients.get(&client_id).map(|c| c.clone());
+        match client {
+            Some(client) => {
+                client.close_with(code, reason).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Feeds the publishes of a recording to a connected client, in order and
+    /// spaced out as they were recorded. The client receives them as
+    /// subscription messages whether or not it is subscribed to the topics.
+    /// Returns how many messages were sent.
+    pub async fn replay(&self, client_id: u16, path: impl AsRef<Path>) -> crate::Result<usize> {
+        let records = TrafficRecorder::load(path).await?;
+        let client = self
+            .clients
+            .get(&client_id)
+            .map(|c| c.clone())
+            .ok_or(ClientError::Disconnected)?;
+        let messages: Vec<_> = records
+            .iter()
+            .filter(|r| r.kind == TrafficKind::Publish)
+            .filter_map(|r| Some((r.offset(), r.as_publish()?)))
+            .collect();
+        let started = tokio::time::Instant::now();
+        let first = messages.first().map_or(Duration::ZERO, |(offset, _)| *offset);
+        for (offset, p) in &messages {
+            let due = started + offset.saturating_sub(first);
+            tokio::time::sleep_until(due).await;
+            client.deliver(p).await;
+        }
+        Ok(messages.len())
+    }
+
+    fn record(&self, client_id: u16, params: &Params) {
+        let Some(recorder) = &self.recorder else {
+            return;
+        };
+        match params {
+            Params::Publish(p) => {
+                recorder.record(TrafficRecord::publish(TrafficKind::Publish, client_id, p));
+            }
+            Params::Subscribe(s) => recorder.record(TrafficRecord::topic(
+                TrafficKind::Subscribe,
+                client_id,
+                s.topic.clone(),
+            )),
+            Params::BatchSubscribe(b) => {
+                for topic in &b.topics {
+                    recorder.record(TrafficRecord::topic(
+                        TrafficKind::Subscribe,
+                        client_id,
+                        topic.clone(),
+                    ));
+                }
+            }
+            Params::Unsubscribe(u) => recorder.record(TrafficRecord::topic(
+                TrafficKind::Unsubscribe,
+                client_id,
+                u.topic.clone(),
+            )),
+            _ => {}
+        }
+    }
+
+    /// Stops accepting connections, closes every client websocket and waits
+    /// for all connection tasks to finish.
+    pub async fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+        let server = self.server.lock().await.take();
+        if let Some(server) = server {
+            if let Err(e) = server.await {
+                error!("mock relay server task failed: {e}");
+            }
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.flush().await;
+        }
+    }
+
     /// The main server loop that accepts incoming connections.
     async fn run(relay: Self, listener: TcpListener) {
+        let mut connections = JoinSet::new();
+        let mut shutdown = relay.shutdown.subscribe();
         loop {
-            match listener.accept().await {
-                Ok((stream, addr)) => {
-                    let me = relay.clone();
-                    tokio::spawn(async move {
-                        me.handle_connection(stream, addr).await;
-                    });
-                }
-                Err(e) => {
-                    error!("Failed to accept connection: {e}");
-                }
-            };
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        let me = relay.clone();
+                        connections.spawn(async move {
+                            me.handle_connection(stream, addr).await;
+                        });
+                    }
+                    Err(e) => {
+                        error!("Failed to accept connection: {e}");
+                    }
+                },
+                () = stopped(&mut shutdown) => break,
+            }
         }
+        drop(listener);
+        while connections.join_next().await.is_some() {}
+        info!("mock relay {} has shutdown", relay.addr);
     }
 
+    /// Sends `payload` to the client after waiting `delay`.
     #[allow(clippy::missing_panics_doc)]
     pub async fn forward(
         payload: Payload,
         ws_sender: Arc<Mutex<SplitSink<WebSocketStream<TcpStream>, Message>>>,
         delay: Duration,
     ) {
+        tokio::time::sleep(delay).await;
         let payload = serde_json::to_string(&payload).expect("this should never happen");
         let mut l = ws_sender.lock().await;
         if let Err(e) = l.send(Message::text(&payload)).await {
             error!("client has closed connection {e}");
         }
-        drop(l);
-        tokio::time::sleep(delay).await;
     }
 
     #[allow(clippy::missing_panics_doc)]
@@ -100,17 +337,103 @@ impl MockRelay {
         }
     }
 
+    #[allow(clippy::missing_panics_doc)]
+    pub async fn handle_error(
+        id: MessageId,
+        ws_sender: Arc<Mutex<SplitSink<WebSocketStream<TcpStream>, Message>>>,
+        error: ErrorData,
+    ) {
+        let payload: Payload = Payload::Response(Response::Error(ErrorResponse::new(id, error)));
+        let payload = serde_json::to_string(&payload).expect("never");
+        let mut ws_sender = ws_sender.lock().await;
+        if ws_sender.send(Message::text(&payload)).await.is_err() {
+            error!("client has closed connection");
+        }
+    }
+
+    /// Rejects the websocket upgrade with `401` when the project ID or JWT is
+    /// not acceptable.
+    #[allow(clippy::result_large_err)]
+    fn authorize(
+        &self,
+        req: &HandshakeRequest,
+        resp: HandshakeResponse,
+        handshake: &mut Handshake,
+    ) -> Result<HandshakeResponse, HandshakeError> {
+        handshake.origin = req
+            .headers()
+            .get(ORIGIN)
+            .and_then(|h| h.to_str().ok())
+            .map(String::from);
+        handshake.user_agent =
+            url::form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+                .find(|(k, _)| k == "ua")
+                .map(|(_, v)| v.into_owned());
+        let authorization = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok());
+        match self
+            .validation
+            .check_handshake(req.uri().query(), authorization)
+        {
+            Ok(audience) => {
+                handshake.audience = Some(audience);
+                Ok(resp)
+            }
+            Err(e) => {
+                warn!("rejecting connection: {}", e.message);
+                let mut err = HandshakeError::new(serde_json::to_string(&e).ok());
+                *err.status_mut() = StatusCode::UNAUTHORIZED;
+                Err(err)
+            }
+        }
+    }
+
+    /// Relay error injected for this request, if any.
+    fn rejection(&self, params: &Params) -> Option<ErrorData> {
+        match params {
+            Params::Publish(p) => self.faults.publish_rejection(&p.topic),
+            Params::Subscribe(s) => self.faults.subscribe_rejection(&s.topic),
+            Params::BatchSubscribe(b) => b
+                .topics
+                .iter()
+                .find_map(|t| self.faults.subscribe_rejection(t)),
+            _ => None,
+        }
+    }
+
     /// Handles individual WebSocket connections.
+    #[allow(clippy::result_large_err)]
     #[tracing::instrument(level = Level::INFO, skip(stream, addr))]
     async fn handle_connection(&self, stream: tokio::net::TcpStream, addr: SocketAddr) {
-        match accept_async(stream).await {
+        let mut handshake = Handshake::default();
+        match accept_hdr_async(stream, |req: &HandshakeRequest, resp| {
+            self.authorize(req, resp, &mut handshake)
+        })
+        .await
+        {
             Ok(ws_stream) => {
                 let (ws_sender, mut ws_receiver) = ws_stream.split();
                 let ws_sender = Arc::new(Mutex::new(ws_sender));
-                let ws_client = WsClient::new(self, addr.port(), ws_sender);
+                let ws_client = WsClient::new(self, addr.port(), ws_sender.clone(), handshake);
                 info!("created new ws client {ws_client}");
                 self.clients.insert(ws_client.id, ws_client);
-                while let Some(msg) = ws_receiver.next().await {
+                let mut shutdown = self.shutdown.subscribe();
+                loop {
+                    let msg = tokio::select! {
+                        msg = ws_receiver.next() => match msg {
+                            Some(msg) => msg,
+                            None => break,
+                        },
+                        () = stopped(&mut shutdown) => {
+                            debug!("closing connection {addr}");
+                            if let Err(e) = ws_sender.lock().await.close().await {
+                                debug!("failed to close {addr}: {e}");
+                            }
+                            break;
+                        }
+                    };
                     match msg {
                         Ok(msg) => {
                             if !msg.is_text() {
@@ -120,7 +443,16 @@ impl MockRelay {
                                 serde_json::from_str::<Payload>(msg.to_text().expect("no"));
                             match payload {
                                 Ok(payload) => match &payload {
-                                    Payload::Request(_) => {
+                                    Payload::Request(req) => {
+                                        if let Err(e) = self.validation.check_request(req) {
+                                            Self::handle_error(req.id, ws_sender.clone(), e).await;
+                                            continue;
+                                        }
+                                        if let Some(e) = self.rejection(&req.params) {
+                                            Self::handle_error(req.id, ws_sender.clone(), e).await;
+                                            continue;
+                                        }
+                                        self.record(addr.port(), &req.params);
                                         let msg = WsPublishedMessage {
                                             client_id: addr.port(),
                                             payload,
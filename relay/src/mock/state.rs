@@ -0,0 +1,32 @@
+use {crate::Topic, serde::Serialize};
+
+/// Snapshot of a [`super::MockRelay`] for inspection.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockRelayState {
+    pub clients: Vec<MockClientState>,
+    pub pending: Vec<MockPendingState>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockClientState {
+    pub id: u16,
+    pub topics: Vec<Topic>,
+    /// `Origin` header of the websocket upgrade request
+    pub origin: Option<String>,
+    /// `ua` query parameter of the websocket upgrade request
+    pub user_agent: Option<String>,
+    /// `aud` claim of the JWT the client connected with
+    pub audience: Option<String>,
+}
+
+/// A publish waiting for a subscriber.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockPendingState {
+    pub topic: Topic,
+    pub tag: u32,
+    pub ttl_secs: u32,
+    pub prompt: bool,
+}
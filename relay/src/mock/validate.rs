@@ -0,0 +1,111 @@
+use {
+    crate::{ProjectId, RelayRpcError, RELAY_ADDRESS},
+    reown_relay_rpc::{
+        jwt::{JwtBasicClaims, VerifyableClaims},
+        rpc::{AuthError, ErrorData, GenericError, Params, PayloadError, PublishError, Request},
+    },
+    std::{collections::HashSet, sync::Arc, time::Duration},
+    tracing::warn,
+};
+
+/// Shortest TTL the relay accepts for a publish.
+pub const MIN_TTL: Duration = Duration::from_secs(10);
+/// Longest TTL the relay accepts for a publish.
+pub const MAX_TTL: Duration = Duration::from_hours(30 * 24);
+/// Default limit on the size of a published message.
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024;
+
+/// What the [`super::MockRelay`] checks before accepting a connection or a
+/// request.
+#[derive(Debug, Clone)]
+pub(super) struct Validation {
+    pub(super) audience: HashSet<String>,
+    /// `None` accepts every well formed project ID
+    pub(super) project_ids: Option<Arc<HashSet<ProjectId>>>,
+    pub(super) max_message_size: usize,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Self {
+            audience: HashSet::from([String::from(RELAY_ADDRESS)]),
+            project_ids: None,
+            max_message_size: MAX_MESSAGE_SIZE,
+        }
+    }
+}
+
+fn auth_error(e: AuthError) -> ErrorData {
+    RelayRpcError::<GenericError>::Auth(e).into()
+}
+
+impl Validation {
+    /// Checks the project ID and JWT of a websocket upgrade request, taken
+    /// from the query string or the `Authorization` header. Returns the
+    /// audience of the JWT.
+    pub(super) fn check_handshake(
+        &self,
+        query: Option<&str>,
+        authorization: Option<&str>,
+    ) -> Result<String, ErrorData> {
+        let mut project_id = None;
+        let mut auth = authorization.and_then(|h| h.strip_prefix("Bearer "));
+        for (key, value) in query
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|kv| kv.split_once('='))
+        {
+            match key {
+                "projectId" => project_id = Some(value),
+                "auth" => auth = Some(value),
+                _ => {}
+            }
+        }
+
+        let project_id = project_id
+            .filter(|p| !p.is_empty())
+            .map(|p| ProjectId::new(Arc::from(p)))
+            .ok_or_else(|| auth_error(AuthError::ProjectIdNotSpecified))?;
+        if project_id.decode().is_err() {
+            return Err(auth_error(AuthError::ProjectNotFound));
+        }
+        if let Some(allowed) = &self.project_ids {
+            if !allowed.contains(&project_id) {
+                return Err(auth_error(AuthError::ProjectNotFound));
+            }
+        }
+
+        let jwt = auth.ok_or_else(|| auth_error(AuthError::MissingJwt))?;
+        JwtBasicClaims::try_from_str(jwt)
+            .and_then(|claims| {
+                claims.verify_basic(&self.audience, None)?;
+                Ok(claims.aud)
+            })
+            .map_err(|e| {
+                warn!("rejecting jwt: {e}");
+                auth_error(AuthError::InvalidJwt)
+            })
+    }
+
+    /// Checks a request the way the relay does before handling it.
+    pub(super) fn check_request(&self, req: &Request) -> Result<(), ErrorData> {
+        req.validate()
+            .map_err(RelayRpcError::<GenericError>::Payload)?;
+        if let Params::Publish(p) = &req.params {
+            if p.message.len() > self.max_message_size {
+                return Err(RelayRpcError::<PublishError>::Payload(
+                    PayloadError::PayloadSizeExceeded,
+                )
+                .into());
+            }
+            let ttl = Duration::from_secs(u64::from(p.ttl_secs));
+            if ttl < MIN_TTL {
+                return Err(RelayRpcError::Handler(PublishError::TtlTooShort).into());
+            }
+            if ttl > MAX_TTL {
+                return Err(RelayRpcError::Handler(PublishError::TtlTooLong).into());
+            }
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ mod proposal;
 mod request;
 mod session;
 mod session_handlers;
+mod stop;
 mod transport;
 
 pub use {
@@ -13,7 +14,17 @@ pub use {
     transport::TransportActor,
 };
 use {
-    crate::{actors::proposal::ProposalActor, rpc::RequestParams, Result},
+    crate::{
+        actors::proposal::ProposalActor,
+        custom::CustomMethods,
+        lifecycle::Lifecycle,
+        middleware::MiddlewareChain,
+        queues::{QueueDepth, QueueLimits},
+        rate_limit::{RateLimiter, RateLimits},
+        rpc::RequestParams,
+        tasks::Tasks,
+        Result,
+    },
     monedero_cipher::Cipher,
     monedero_domain::Topic,
     monedero_relay::Client,
@@ -28,6 +39,9 @@ pub struct Actors {
     transport_actor: Address<TransportActor>,
     session_actor: Address<SessionRequestHandlerActor>,
     proposal_actor: Address<ProposalActor>,
+    lifecycle: Lifecycle,
+    limiter: RateLimiter,
+    tasks: Tasks,
 }
 
 pub struct ClearPairing;
@@ -36,6 +50,8 @@ pub struct SendRequest(pub(crate) Topic, pub(crate) RequestParams);
 pub struct SessionPing;
 pub struct AddRequest;
 pub struct ClearSession(pub Topic);
+/// Stop the actor after the messages queued before this one
+pub struct Stop;
 
 impl Display for SendRequest {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -48,6 +64,21 @@ impl Display for SendRequest {
     }
 }
 
+/// What [`crate::ReownBuilder`] configures on the actors and the relay
+/// handler.
+///
+/// Peer requests that are not taken get an error response with a code that
+/// is not part of the Sign API spec, see [`crate::rpc::OverloadErrors`]:
+/// `11000` when [`ActorConfig::queues`] are full and `11001` when the peer
+/// is over [`ActorConfig::rate_limits`].
+#[derive(Clone, Default)]
+pub struct ActorConfig {
+    pub queues: QueueLimits,
+    pub rate_limits: RateLimits,
+    pub middleware: MiddlewareChain,
+    pub custom: CustomMethods,
+}
+
 /// Get number of sessions/pair managers are active
 pub struct RegisteredComponents;
 
@@ -62,29 +93,61 @@ pub fn actor_spawn<A>(actor: A) -> Address<A>
 where
     A: Actor<Stop = ()>,
 {
+    actor_spawn_with(actor, None)
+}
+
+/// Spawn with a mailbox of `capacity`, senders wait while it is full.
+pub fn actor_spawn_with<A>(actor: A, capacity: Option<usize>) -> Address<A>
+where
+    A: Actor<Stop = ()>,
+{
+    let mailbox = capacity.map_or_else(Mailbox::unbounded, |c| Mailbox::bounded(c.max(1)));
     #[cfg(not(target_arch = "wasm32"))]
-    return xtra::spawn_tokio(actor, Mailbox::unbounded());
+    return xtra::spawn_tokio(actor, mailbox);
     #[cfg(target_arch = "wasm32")]
-    return xtra::spawn_wasm_bindgen(actor, Mailbox::unbounded());
+    return xtra::spawn_wasm_bindgen(actor, mailbox);
 }
 
 impl Actors {
-    pub(crate) fn init(cipher: Cipher) -> Self {
-        let inbound_response_actor = actor_spawn(InboundResponseActor::default());
-        let transport_actor = actor_spawn(TransportActor::new(
-            cipher.clone(),
-            inbound_response_actor.clone(),
-        ));
-        let session_actor = actor_spawn(SessionRequestHandlerActor::new(
-            transport_actor.clone(),
-            cipher,
-        ));
-        let proposal_actor = actor_spawn(ProposalActor::new(transport_actor.clone()));
-        let request_actor = actor_spawn(RequestHandlerActor::new(
-            transport_actor.clone(),
-            session_actor.clone(),
-            proposal_actor.clone(),
-        ));
+    pub(crate) fn init(cipher: Cipher, config: &ActorConfig) -> Self {
+        let lifecycle = Lifecycle::default();
+        let limiter = RateLimiter::new(config.rate_limits.clone());
+        let tasks = Tasks::default();
+        let capacity = config.queues.mailbox;
+        let inbound_response_actor = actor_spawn_with(InboundResponseActor::default(), capacity);
+        let transport_actor = actor_spawn_with(
+            TransportActor::new(
+                cipher.clone(),
+                inbound_response_actor.clone(),
+                tasks.clone(),
+                config.middleware.clone(),
+                config.custom.clone(),
+            ),
+            capacity,
+        );
+        let session_actor = actor_spawn_with(
+            SessionRequestHandlerActor::new(
+                transport_actor.clone(),
+                cipher,
+                lifecycle.clone(),
+                limiter.clone(),
+                tasks.clone(),
+            ),
+            capacity,
+        );
+        let proposal_actor =
+            actor_spawn_with(ProposalActor::new(transport_actor.clone()), capacity);
+        let request_actor = actor_spawn_with(
+            RequestHandlerActor::new(
+                transport_actor.clone(),
+                session_actor.clone(),
+                proposal_actor.clone(),
+                tasks.clone(),
+                limiter.clone(),
+                lifecycle.clone(),
+            ),
+            capacity,
+        );
 
         Self {
             inbound_response_actor,
@@ -92,6 +155,9 @@ impl Actors {
             transport_actor,
             session_actor,
             proposal_actor,
+            lifecycle,
+            limiter,
+            tasks,
         }
     }
 }
@@ -116,4 +182,29 @@ impl Actors {
     pub fn proposal(&self) -> Address<ProposalActor> {
         self.proposal_actor.clone()
     }
+
+    pub(crate) fn lifecycle(&self) -> Lifecycle {
+        self.lifecycle.clone()
+    }
+
+    pub(crate) fn limiter(&self) -> RateLimiter {
+        self.limiter.clone()
+    }
+
+    pub(crate) fn tasks(&self) -> Tasks {
+        self.tasks.clone()
+    }
+
+    /// Messages waiting in each mailbox, the inbound channel fields are left
+    /// at zero.
+    pub(crate) fn queue_depth(&self) -> QueueDepth {
+        QueueDepth {
+            request_actor: self.request_actor.len(),
+            proposal_actor: self.proposal_actor.len(),
+            session_actor: self.session_actor.len(),
+            transport_actor: self.transport_actor.len(),
+            response_actor: self.inbound_response_actor.len(),
+            ..QueueDepth::default()
+        }
+    }
 }
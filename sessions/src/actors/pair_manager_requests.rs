@@ -2,7 +2,6 @@ use {
     crate::{
         actors::RequestHandlerActor,
         rpc::{IntoUnknownError, RpcResponse, RpcResponsePayload},
-        spawn_task,
         PairingManager,
         Result,
         Topic,
@@ -16,7 +15,7 @@ impl RequestHandlerActor {
         let me = self.clone();
         let id = resp.id;
         let topic = resp.topic.clone();
-        spawn_task(async move {
+        self.tasks.spawn_flushed(async move {
             if let Err(err) = me.responder.send(resp).await {
                 warn!(
                     "Failed to send response for id {} on topic {} {}",
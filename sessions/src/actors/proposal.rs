@@ -19,8 +19,8 @@ use {
 
 #[derive(Clone, Actor)]
 pub struct ProposalActor {
-    dapp: Option<Address<Dapp>>,
-    wallet: Option<Address<Wallet>>,
+    pub(super) dapp: Option<Address<Dapp>>,
+    pub(super) wallet: Option<Address<Wallet>>,
     pub(super) responder: Address<TransportActor>,
 }
 
@@ -7,10 +7,21 @@ use {
             RegisteredComponents,
             TransportActor,
         },
-        rpc::{Request, RequestParams, RpcRequest},
-        spawn_task,
+        lifecycle::Lifecycle,
+        rate_limit::RateLimiter,
+        rpc::{
+            OverloadErrors,
+            RelayProtocolMetadata,
+            Request,
+            RequestParams,
+            RpcRequest,
+            RpcResponse,
+            RpcResponsePayload,
+        },
+        tasks::Tasks,
         PairingManager,
         Result,
+        SessionLifecycleEvent,
     },
     monedero_relay::Client,
     std::fmt::{Debug, Formatter},
@@ -24,6 +35,9 @@ pub struct RequestHandlerActor {
     pub(super) responder: Address<TransportActor>,
     session_handler: Address<SessionRequestHandlerActor>,
     proposal_handler: Address<ProposalActor>,
+    pub(super) tasks: Tasks,
+    limiter: RateLimiter,
+    lifecycle: Lifecycle,
 }
 impl Debug for RequestHandlerActor {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -69,13 +83,47 @@ impl RequestHandlerActor {
         responder: Address<TransportActor>,
         session_handler: Address<SessionRequestHandlerActor>,
         proposal_handler: Address<ProposalActor>,
+        tasks: Tasks,
+        limiter: RateLimiter,
+        lifecycle: Lifecycle,
     ) -> Self {
         Self {
             pair_managers: None,
             responder,
             session_handler,
             proposal_handler,
+            tasks,
+            limiter,
+            lifecycle,
+        }
+    }
+
+    /// Answer with [`OverloadErrors::RateLimited`] if the peer is over its limit.
+    async fn rate_limited(&self, message: &RpcRequest) -> bool {
+        let params = &message.payload.params;
+        let method = params.method();
+        if self
+            .limiter
+            .allow(&message.topic, method, params.irn_metadata().prompt)
+        {
+            return false;
+        }
+        warn!("rate limiting {method} on {}", message.topic);
+        let response = RpcResponse {
+            id: message.payload.id,
+            topic: message.topic.clone(),
+            payload: RpcResponsePayload::Error(
+                params.error_response(OverloadErrors::RateLimited.into()),
+            ),
+        };
+        if let Err(e) = self.responder.send(response).detach().await {
+            warn!("failed to send rate limited response {e}");
         }
+        self.lifecycle.emit(SessionLifecycleEvent::RateLimited {
+            topic: message.topic.clone(),
+            method: String::from(method),
+        });
+        true
     }
 
     pub(crate) async fn send_client(&self, relay: Client) -> Result<()> {
@@ -91,6 +139,9 @@ impl Handler<RpcRequest> for RequestHandlerActor {
         let id = message.payload.id;
         let topic = message.topic.clone();
         debug!("handing request {id}");
+        if self.rate_limited(&message).await {
+            return;
+        }
         match message.payload.params {
             RequestParams::PairDelete(args) => {
                 self.handle_pair_mgr_request(id, topic.clone(), args).await;
@@ -111,12 +162,9 @@ impl Handler<RpcRequest> for RequestHandlerActor {
                         params: RequestParams::SessionPropose(args),
                     },
                 };
-                let proposal_handler = self.proposal_handler.clone();
-                spawn_task(async move {
-                    if let Err(e) = proposal_handler.send(rpc).await {
-                        warn!("failed to send proposal {e}");
-                    }
-                });
+                if let Err(e) = self.proposal_handler.send(rpc).detach().await {
+                    warn!("failed to send proposal {e}");
+                }
             }
             RequestParams::SessionSettle(args) => {
                 let rpc = RpcRequest {
@@ -127,20 +175,15 @@ impl Handler<RpcRequest> for RequestHandlerActor {
                         params: RequestParams::SessionSettle(args),
                     },
                 };
-                let proposal_handler = self.proposal_handler.clone();
-                spawn_task(async move {
-                    if let Err(e) = proposal_handler.send(rpc).await {
-                        warn!("failed to send proposal {e}");
-                    }
-                });
+                if let Err(e) = self.proposal_handler.send(rpc).detach().await {
+                    warn!("failed to send proposal {e}");
+                }
             }
             _ => {
-                let session_handlers = self.session_handler.clone();
-                spawn_task(async move {
-                    if let Err(e) = session_handlers.send(message).await {
-                        warn!("failed to send to session handler actor {e}");
-                    }
-                });
+                // only waits for room in the mailbox, not for the handler
+                if let Err(e) = self.session_handler.send(message).detach().await {
+                    warn!("failed to send to session handler actor {e}");
+                }
             }
         }
     }
@@ -1,6 +1,8 @@
 use {
     crate::{
         actors::{actor_spawn, ClearPairing, ClearSession, SessionPing, TransportActor},
+        lifecycle::{self, Expiries, Lifecycle, SessionLifecycleEvent},
+        rate_limit::RateLimiter,
         rpc::{
             ErrorParams,
             RequestParams,
@@ -9,9 +11,10 @@ use {
             RpcRequest,
             RpcResponse,
             RpcResponsePayload,
+            SdkErrors,
         },
-        session::ClientSession,
-        spawn_task,
+        session::{ClientSession, SessionState},
+        tasks::Tasks,
         RegisteredComponents,
         Topic,
     },
@@ -32,6 +35,11 @@ pub struct SessionRequestHandlerActor {
     pub(super) sessions: Arc<DashMap<Topic, Address<ClientSession>>>,
     pub(super) responder: Address<TransportActor>,
     pub(super) cipher: Cipher,
+    pub(super) lifecycle: Lifecycle,
+    pub(super) expiries: Expiries,
+    pub(super) settlements: Arc<DashMap<Topic, SessionState>>,
+    pub(super) limiter: RateLimiter,
+    pub(super) tasks: Tasks,
 }
 
 impl Debug for SessionRequestHandlerActor {
@@ -41,11 +49,22 @@ impl Debug for SessionRequestHandlerActor {
 }
 
 impl SessionRequestHandlerActor {
-    pub(crate) fn new(responder: Address<TransportActor>, cipher: Cipher) -> Self {
+    pub(crate) fn new(
+        responder: Address<TransportActor>,
+        cipher: Cipher,
+        lifecycle: Lifecycle,
+        limiter: RateLimiter,
+        tasks: Tasks,
+    ) -> Self {
         Self {
             sessions: Arc::new(DashMap::new()),
             responder,
             cipher,
+            lifecycle,
+            expiries: Expiries::default(),
+            settlements: Arc::new(DashMap::new()),
+            limiter,
+            tasks,
         }
     }
 }
@@ -62,7 +81,10 @@ impl Handler<ClearPairing> for SessionRequestHandlerActor {
     type Return = ();
 
     async fn handle(&mut self, _message: ClearPairing, _ctx: &mut Context<Self>) -> Self::Return {
-        self.sessions.clear();
+        let topics: Vec<Topic> = self.sessions.iter().map(|s| s.key().clone()).collect();
+        for topic in topics {
+            self.handle_session_delete(topic).await;
+        }
     }
 }
 
@@ -74,12 +96,29 @@ impl Handler<ClientSession> for SessionRequestHandlerActor {
         let topic = message.topic();
         let addr = actor_spawn(message.clone());
         self.sessions.insert(topic.clone(), addr);
-        if let Err(e) = self
-            .cipher
-            .set_settlement(&topic, (*message.settled).clone())
-        {
+        let settled = message.settled();
+        if let Err(e) = self.cipher.set_settlement(&topic, settled.clone()) {
             error!("failed to set settlement for {topic} error:{e}");
         }
+        self.settlements.insert(topic.clone(), message.state());
+        if self
+            .expiries
+            .insert(topic.clone(), settled.expiry)
+            .is_none()
+        {
+            let me = self.clone();
+            lifecycle::watch_expiry(
+                &self.tasks,
+                topic,
+                self.expiries.clone(),
+                |topic| async move {
+                    warn!("session {topic} has expired");
+                    if me.handle_session_delete(topic.clone()).await {
+                        me.lifecycle.emit(SessionLifecycleEvent::Expired { topic });
+                    }
+                },
+            );
+        }
     }
 }
 
@@ -98,38 +137,62 @@ impl Handler<RegisteredComponents> for SessionRequestHandlerActor {
 impl Handler<RpcRequest> for SessionRequestHandlerActor {
     type Return = ();
 
+    #[allow(clippy::too_many_lines)]
     #[tracing::instrument(skip(_ctx), level = "info", fields(message = message.to_string()))]
     async fn handle(&mut self, message: RpcRequest, _ctx: &mut Context<Self>) -> Self::Return {
         match message.payload.params {
-            RequestParams::SessionUpdate(_args) => {
+            RequestParams::SessionUpdate(args) => {
+                let payload = match self.handle_session_update(&message.topic, args.namespaces) {
+                    Ok(()) => {
+                        RpcResponsePayload::Success(ResponseParamsSuccess::SessionUpdate(true))
+                    }
+                    Err(e) => {
+                        RpcResponsePayload::Error(ResponseParamsError::SessionUpdate(e.into()))
+                    }
+                };
                 let response = RpcResponse {
                     id: message.payload.id,
                     topic: message.topic,
-                    payload: RpcResponsePayload::Success(ResponseParamsSuccess::SessionUpdate(
-                        true,
-                    )),
+                    payload,
                 };
                 if let Err(e) = self.responder.send(response).await {
                     warn!("responder actor is not responding {e}");
                 }
             }
-            RequestParams::SessionExtend(_args) => {
+            RequestParams::SessionExtend(args) => {
+                let payload = if self.handle_session_extend(&message.topic, args.expiry) {
+                    RpcResponsePayload::Success(ResponseParamsSuccess::SessionExtend(true))
+                } else {
+                    RpcResponsePayload::Error(ResponseParamsError::SessionExtend(
+                        SdkErrors::InvalidExtendRequest.into(),
+                    ))
+                };
                 let response = RpcResponse {
                     id: message.payload.id,
                     topic: message.topic,
-                    payload: RpcResponsePayload::Success(ResponseParamsSuccess::SessionExtend(
-                        true,
-                    )),
+                    payload,
                 };
                 if let Err(e) = self.responder.send(response).await {
                     warn!("responder actor is not responding {e}");
                 }
             }
             RequestParams::SessionRequest(args) => {
+                self.lifecycle.emit(SessionLifecycleEvent::RequestReceived {
+                    topic: message.topic.clone(),
+                    request: args.clone(),
+                });
                 self.handle_session_request(message.payload.id, message.topic, args)
                     .await;
             }
-            RequestParams::SessionEvent(_args) => {
+            RequestParams::Custom(args) => {
+                self.handle_session_request(message.payload.id, message.topic, args)
+                    .await;
+            }
+            RequestParams::SessionEvent(args) => {
+                self.lifecycle.emit(SessionLifecycleEvent::EventReceived {
+                    topic: message.topic.clone(),
+                    event: args,
+                });
                 let response = RpcResponse {
                     id: message.payload.id,
                     topic: message.topic,
@@ -139,7 +202,7 @@ impl Handler<RpcRequest> for SessionRequestHandlerActor {
                     warn!("responder actor is not responding {e}");
                 }
             }
-            RequestParams::SessionDelete(_args) => {
+            RequestParams::SessionDelete(args) => {
                 if let Err(e) = self
                     .responder
                     .send(RpcResponse {
@@ -154,10 +217,10 @@ impl Handler<RpcRequest> for SessionRequestHandlerActor {
                     warn!("failed to send response back for delete request {e}");
                 }
                 let me = self.clone();
-                spawn_task(async move {
+                self.tasks.spawn_flushed(async move {
                     // give some time for the response above, before I unsubscribe.
                     tokio::time::sleep(Duration::from_millis(300)).await;
-                    me.handle_session_delete(message.topic).await;
+                    me.handle_peer_delete(message.topic, args).await;
                 });
             }
             RequestParams::SessionPing(()) => {
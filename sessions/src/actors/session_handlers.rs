@@ -1,15 +1,21 @@
 use {
     crate::{
         actors::{SessionRequestHandlerActor, Unsubscribe},
-        rpc::{IntoUnknownError, RpcResponse, RpcResponsePayload},
+        lifecycle::SessionLifecycleEvent,
+        rpc::{IntoUnknownError, RpcResponse, RpcResponsePayload, SdkErrors, SessionDeleteRequest},
+        session::SessionClosed,
         ClientSession,
         Result,
         Topic,
     },
-    monedero_domain::MessageId,
-    tracing::warn,
+    monedero_domain::{namespaces::Namespaces, MessageId, SessionSettled},
+    std::sync::PoisonError,
+    tracing::{info, warn},
 };
 
+/// Furthest a peer can push the expiry of a session, from now.
+const MAX_EXTEND_SECS: i64 = 7 * 24 * 60 * 60;
+
 impl SessionRequestHandlerActor {
     pub(super) async fn send_response(&self, resp: RpcResponse) {
         let id = resp.id;
@@ -47,12 +53,111 @@ impl SessionRequestHandlerActor {
         Ok(())
     }
 
-    pub(super) async fn handle_session_delete(&self, topic: Topic) {
-        self.sessions.remove(&topic);
+    /// Returns false if there was no session for `topic`.
+    pub(super) async fn handle_session_delete(&self, topic: Topic) -> bool {
+        let removed = self.sessions.remove(&topic);
+        self.expiries.remove(&topic);
+        self.settlements.remove(&topic);
+        self.limiter.forget(&topic);
         if let Err(e) = self.responder.send(Unsubscribe(topic.clone())).await {
             warn!("failed to unsubscribe to {topic} '{e}'");
         }
         let _ = self.cipher.delete_session(&topic);
+        let Some((_, session)) = removed else {
+            return false;
+        };
+        let _ = session.send(SessionClosed).await;
+        true
+    }
+
+    pub(super) async fn handle_peer_delete(&self, topic: Topic, request: SessionDeleteRequest) {
+        let session = self.sessions.get(&topic).map(|s| s.clone());
+        if !self.handle_session_delete(topic.clone()).await {
+            return;
+        }
+        info!("peer deleted session {topic}: {}", request.message);
+        self.lifecycle
+            .emit(SessionLifecycleEvent::DeletedByPeer { topic });
+        if let Some(session) = session {
+            if let Err(e) = session.send(request).await {
+                warn!("failed to notify session delete handlers: {e}");
+            }
+        }
+    }
+
+    /// Change the live session and its stored settlement with `update`.
+    /// Returns false if there was no session for `topic`.
+    fn update_settlement(&self, topic: &Topic, update: impl FnOnce(&mut SessionSettled)) -> bool {
+        let Some(state) = self.settlements.get(topic).map(|s| s.clone()) else {
+            warn!("no session for {topic}");
+            return false;
+        };
+        let settled = {
+            let mut settled = state.settled.write().unwrap_or_else(PoisonError::into_inner);
+            update(&mut settled);
+            settled.clone()
+        };
+        if let Err(e) = self.cipher.set_settlement(topic, settled) {
+            warn!("failed to store settlement for {topic}: {e}");
+        }
+        true
+    }
+
+    /// Only the controller (the wallet) can update a session, and the new
+    /// namespaces must still cover what the dapp required.
+    pub(super) fn handle_session_update(
+        &self,
+        topic: &Topic,
+        namespaces: Namespaces,
+    ) -> std::result::Result<(), SdkErrors> {
+        let Some(state) = self.settlements.get(topic).map(|s| s.clone()) else {
+            warn!("no session for {topic}");
+            return Err(SdkErrors::InvalidUpdateRequest);
+        };
+        if state.controller {
+            warn!("rejecting update of {topic}, the peer is not the controller");
+            return Err(SdkErrors::UnauthorizedUpdateRequest);
+        }
+        if !namespaces.satisfies(&state.required) {
+            warn!("rejecting update of {topic}, required namespaces are missing");
+            return Err(SdkErrors::InvalidUpdateRequest);
+        }
+        if !self.update_settlement(topic, |settled| {
+            settled.namespaces = namespaces.clone();
+        }) {
+            return Err(SdkErrors::InvalidUpdateRequest);
+        }
+        self.lifecycle.emit(SessionLifecycleEvent::Updated {
+            topic: topic.clone(),
+            namespaces,
+        });
+        Ok(())
+    }
+
+    /// Returns false if `expiry` is before the current one or more than 7
+    /// days ahead, as the Sign API requires.
+    pub(super) fn handle_session_extend(&self, topic: &Topic, expiry: u64) -> bool {
+        let Some(current) = self.expiries.get(topic) else {
+            warn!("no session for {topic}");
+            return false;
+        };
+        let max = chrono::Utc::now().timestamp() + MAX_EXTEND_SECS;
+        let extended = match i64::try_from(expiry) {
+            Ok(extended) if (current..=max).contains(&extended) => extended,
+            _ => {
+                warn!("rejecting extend of {topic} to {expiry}");
+                return false;
+            }
+        };
+        if !self.update_settlement(topic, |settled| settled.expiry = extended) {
+            return false;
+        }
+        self.expiries.extend(topic, extended);
+        self.lifecycle.emit(SessionLifecycleEvent::Extended {
+            topic: topic.clone(),
+            expiry,
+        });
+        true
     }
 
     pub(super) async fn handle_session_request<M>(&self, id: MessageId, topic: Topic, request: M)
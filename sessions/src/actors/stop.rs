@@ -0,0 +1,102 @@
+use {
+    super::{
+        proposal::ProposalActor,
+        Actors,
+        InboundResponseActor,
+        RequestHandlerActor,
+        SessionRequestHandlerActor,
+        Stop,
+        TransportActor,
+    },
+    crate::{session::SessionClosed, ClientSession, Dapp, PairingManager, Wallet},
+    tracing::debug,
+    xtra::prelude::*,
+};
+
+macro_rules! stop_handler {
+    ($($actor:ty),+ $(,)?) => {
+        $(
+            impl Handler<Stop> for $actor {
+                type Return = ();
+
+                async fn handle(&mut self, _message: Stop, ctx: &mut Context<Self>) -> Self::Return {
+                    ctx.stop_self();
+                }
+            }
+        )+
+    };
+}
+
+stop_handler!(
+    InboundResponseActor,
+    TransportActor,
+    ClientSession,
+    Dapp,
+    Wallet,
+    PairingManager,
+);
+
+impl Handler<Stop> for RequestHandlerActor {
+    type Return = ();
+
+    async fn handle(&mut self, _message: Stop, ctx: &mut Context<Self>) -> Self::Return {
+        if let Some(mgr) = self.pair_managers.take() {
+            stop(&mgr).await;
+        }
+        ctx.stop_self();
+    }
+}
+
+impl Handler<Stop> for ProposalActor {
+    type Return = ();
+
+    async fn handle(&mut self, _message: Stop, ctx: &mut Context<Self>) -> Self::Return {
+        if let Some(dapp) = self.dapp.take() {
+            stop(&dapp).await;
+        }
+        if let Some(wallet) = self.wallet.take() {
+            stop(&wallet).await;
+        }
+        ctx.stop_self();
+    }
+}
+
+impl Handler<Stop> for SessionRequestHandlerActor {
+    type Return = ();
+
+    async fn handle(&mut self, _message: Stop, ctx: &mut Context<Self>) -> Self::Return {
+        // the expiry watchers end once their topic is gone
+        self.expiries.clear();
+        self.settlements.clear();
+        let sessions: Vec<Address<ClientSession>> =
+            self.sessions.iter().map(|s| s.value().clone()).collect();
+        self.sessions.clear();
+        for session in sessions {
+            let _ = session.send(SessionClosed).await;
+            stop(&session).await;
+        }
+        ctx.stop_self();
+    }
+}
+
+async fn stop<A>(addr: &Address<A>)
+where
+    A: Handler<Stop, Return = ()>,
+{
+    // fails if the actor has already stopped, which is what we want anyway
+    let _ = addr.send(Stop).await;
+    addr.join().await;
+}
+
+impl Actors {
+    /// Stop every actor, starting with those that take inbound requests so
+    /// that their responses are still handed to the transport.
+    pub(crate) async fn stop(&self) {
+        debug!("stopping actors");
+        stop(&self.request_actor).await;
+        stop(&self.proposal_actor).await;
+        stop(&self.session_actor).await;
+        stop(&self.transport_actor).await;
+        stop(&self.inbound_response_actor).await;
+    }
+}
@@ -1,15 +1,18 @@
 use {
     crate::{
         actors::{AddRequest, ClearPairing, InboundResponseActor, SendRequest, Unsubscribe},
+        custom::CustomMethods,
+        middleware::{self, Flow, MiddlewareChain},
         rpc::{
             IrnMetadata,
             RelayProtocolMetadata,
             Request,
+            RequestParams,
             Response,
             RpcResponse,
             RpcResponsePayload,
         },
-        spawn_task,
+        tasks::Tasks,
         Result,
     },
     monedero_cipher::Cipher,
@@ -30,6 +33,9 @@ pub struct TransportActor {
     cipher: Cipher,
     relay: Option<Client>,
     inbound_response_actor: Address<InboundResponseActor>,
+    tasks: Tasks,
+    middleware: MiddlewareChain,
+    custom: CustomMethods,
 }
 
 impl Debug for TransportActor {
@@ -104,11 +110,17 @@ impl TransportActor {
     pub(crate) fn new(
         cipher: Cipher,
         inbound_response_actor: Address<InboundResponseActor>,
+        tasks: Tasks,
+        middleware: MiddlewareChain,
+        custom: CustomMethods,
     ) -> Self {
         Self {
             cipher,
             inbound_response_actor,
             relay: None,
+            tasks,
+            middleware,
+            custom,
         }
     }
 }
@@ -136,7 +148,8 @@ impl Handler<RpcResponse> for TransportActor {
     async fn handle(&mut self, message: RpcResponse, _ctx: &mut Context<Self>) -> Self::Return {
         let relay = self.relay.clone().ok_or(crate::Error::NoClient)?;
         let cipher = self.cipher.clone();
-        spawn_task(async move {
+        // shutdown waits for responses that are on their way out
+        self.tasks.spawn_flushed(async move {
             send_response(message, cipher, relay).await;
         });
         Ok(())
@@ -149,10 +162,30 @@ impl Handler<SendRequest> for TransportActor {
     #[tracing::instrument(skip(_ctx), level = "info", fields(message = message.to_string()))]
     async fn handle(&mut self, message: SendRequest, _ctx: &mut Context<Self>) -> Self::Return {
         let relay = self.relay.as_ref().ok_or(crate::Error::NoClient)?;
+        if let RequestParams::Custom(custom) = &message.1 {
+            // responses with unregistered tags would be dropped
+            if self.custom.get(&custom.method).is_none() {
+                return Err(crate::Error::InvalidCustomMethod(format!(
+                    "{} is not registered",
+                    custom.method
+                )));
+            }
+        }
         let (id, rx) = self.inbound_response_actor.send(AddRequest).await?;
 
         let topic = message.0;
-        let params = message.1;
+        let requested_ttl = Duration::from_secs(message.1.irn_metadata().ttl);
+        let params = match self.middleware.outbound_request(&topic, message.1).await {
+            Flow::Next(params) => params,
+            Flow::Stop(error) => {
+                debug!("middleware stopped request {id}");
+                self.inbound_response_actor
+                    .send(middleware::error_response(id, &error))
+                    .await?;
+                // the error response is already waiting in `rx`
+                return Ok((id, requested_ttl, rx));
+            }
+        };
         let irn_metadata = params.irn_metadata();
         let request = Request::new(id, params);
         let encrypted = self.cipher.encode(&topic, &request)?;
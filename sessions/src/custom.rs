@@ -0,0 +1,111 @@
+use {
+    crate::{
+        rpc::{CustomMetadata, ErrorParams, IrnMetadata, Payload, SdkErrors},
+        Error,
+        Result,
+    },
+    async_trait::async_trait,
+    dashmap::DashMap,
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::Value,
+    std::{marker::PhantomData, sync::Arc},
+};
+
+/// An app specific JSON-RPC method, sent over an established session.
+///
+/// Register it on both sides with [`crate::ReownBuilder::custom_method`],
+/// call it with [`crate::ClientSession::request`] and answer it with
+/// [`crate::ClientSession::on_request`]. Tags must be outside of the Sign
+/// API range 1000..=1115.
+pub trait CustomMethod: Send + Sync + 'static {
+    const METHOD: &'static str;
+    const REQUEST: IrnMetadata;
+    const RESPONSE: IrnMetadata;
+    type Params: Serialize + DeserializeOwned + Send + Sync + 'static;
+    type Result: Serialize + DeserializeOwned + Send + Sync + 'static;
+}
+
+#[async_trait]
+pub trait CustomHandler<M: CustomMethod>: Send + Sync + 'static {
+    async fn handle(&self, params: M::Params) -> std::result::Result<M::Result, ErrorParams>;
+}
+
+/// [`CustomHandler`] without the types, as kept by the session.
+#[async_trait]
+pub trait ErasedHandler: Send + Sync + 'static {
+    async fn call(&self, params: Value) -> std::result::Result<Value, ErrorParams>;
+}
+
+pub struct TypedHandler<M, H> {
+    handler: H,
+    method: PhantomData<fn() -> M>,
+}
+
+impl<M, H> TypedHandler<M, H> {
+    pub const fn new(handler: H) -> Self {
+        Self {
+            handler,
+            method: PhantomData,
+        }
+    }
+}
+
+fn invalid(e: &serde_json::Error) -> ErrorParams {
+    ErrorParams {
+        message: format!("invalid params: {e}"),
+        ..SdkErrors::InvalidMethod.into()
+    }
+}
+
+#[async_trait]
+impl<M: CustomMethod, H: CustomHandler<M>> ErasedHandler for TypedHandler<M, H> {
+    async fn call(&self, params: Value) -> std::result::Result<Value, ErrorParams> {
+        let params: M::Params = serde_json::from_value(params).map_err(|e| invalid(&e))?;
+        let result = self.handler.handle(params).await?;
+        serde_json::to_value(result).map_err(|e| ErrorParams {
+            message: e.to_string(),
+            ..ErrorParams::unknown()
+        })
+    }
+}
+
+/// Custom methods known to a [`crate::PairingManager`], by method name.
+#[derive(Clone, Default)]
+pub struct CustomMethods(Arc<DashMap<String, CustomMetadata>>);
+
+impl CustomMethods {
+    #[allow(clippy::result_large_err)]
+    pub fn register(&self, method: &str, metadata: CustomMetadata) -> Result<()> {
+        let tags = [metadata.request.tag, metadata.response.tag];
+        if tags.into_iter().any(Payload::irn_tag_in_range) || method.starts_with("wc_") {
+            return Err(Error::InvalidCustomMethod(format!(
+                "{method} uses a Sign API tag or name"
+            )));
+        }
+        if self.0.contains_key(method) || tags.into_iter().any(|t| self.has_tag(t)) {
+            return Err(Error::InvalidCustomMethod(format!(
+                "{method} or its tags are already registered"
+            )));
+        }
+        self.0.insert(String::from(method), metadata);
+        Ok(())
+    }
+
+    pub fn get(&self, method: &str) -> Option<CustomMetadata> {
+        self.0.get(method).map(|m| *m)
+    }
+
+    pub fn has_tag(&self, tag: u32) -> bool {
+        self.0
+            .iter()
+            .any(|m| m.request.tag == tag || m.response.tag == tag)
+    }
+
+    /// Metadata of the method that requests are sent with `tag`.
+    pub fn by_request_tag(&self, tag: u32) -> Option<CustomMetadata> {
+        self.0
+            .iter()
+            .find(|m| m.request.tag == tag)
+            .map(|m| *m.value())
+    }
+}
@@ -2,9 +2,9 @@ mod session_settle;
 
 use {
     crate::{
+        lifecycle::SessionLifecycleEvent,
         rpc::{Metadata, RequestParams, SessionProposeRequest, SessionProposeResponse},
         session::{Category, PendingSession},
-        spawn_task,
         Error::NoPairingTopic,
         PairingManager,
         ProposeFuture,
@@ -72,7 +72,7 @@ fn public_key(pairing: &Pairing) -> String {
 
 async fn finalize_restore(dapp: Dapp, settled: SessionSettled) -> Result<()> {
     dapp.pending
-        .settled(&dapp.manager, settled, Category::Dapp, None)
+        .settled(&dapp.manager, settled, Category::Dapp, None, None)
         .await?;
     Ok(())
 }
@@ -96,13 +96,14 @@ impl Dapp {
         &self,
         settlement: SessionSettled,
         handlers: T,
+        required: Namespaces,
     ) -> Result<(Pairing, ProposeFuture)> {
         info!("dapp session restore");
 
         let pairing = self.manager.pairing().ok_or(NoPairingTopic)?;
-        let rx = self.pending.add(pairing.topic.clone(), handlers);
+        let rx = self.pending.add(pairing.topic.clone(), handlers, required);
         let dapp = self.clone();
-        spawn_task(async move {
+        self.manager.actors().tasks().spawn(async move {
             if let Err(e) = finalize_restore(dapp, settlement).await {
                 error!("failed to finalize session restore! {e}");
             }
@@ -127,7 +128,7 @@ impl Dapp {
         let namespaces: Namespaces = chains.into();
 
         if let Some(settled) = self.manager.find_session(&namespaces) {
-            let (p, cs) = self.restore_session(settled, handlers)?;
+            let (p, cs) = self.restore_session(settled, handlers, namespaces)?;
             return Ok((p, cs, true));
         }
 
@@ -136,17 +137,29 @@ impl Dapp {
         // handle same pairing session
         let pairing = Pairing::default();
         self.manager.set_pairing(pairing.clone()).await?;
-        let rx = self.pending.add(pairing.topic.clone(), handlers);
+        let rx = self
+            .pending
+            .add(pairing.topic.clone(), handlers, namespaces.clone());
         let pk = public_key(&pairing);
         let params = RequestParams::SessionPropose(SessionProposeRequest::new(
             self.md.clone(),
             pk,
-            namespaces,
+            namespaces.clone(),
             None,
         ));
+        self.manager
+            .actors()
+            .lifecycle()
+            .emit(SessionLifecycleEvent::Proposed {
+                pairing_topic: pairing.topic.clone(),
+                required_namespaces: namespaces,
+            });
         let dapp = self.clone();
         let topic = pairing.topic.clone();
-        spawn_task(async move { begin_settlement_flow(dapp, topic, params).await });
+        self.manager
+            .actors()
+            .tasks()
+            .spawn(async move { begin_settlement_flow(dapp, topic, params).await });
         Ok((pairing, ProposeFuture::new(rx), false))
     }
 
@@ -12,7 +12,7 @@ use {
 impl Dapp {
     async fn process_settlement(&self, settled: SessionSettled) -> Result<()> {
         self.pending
-            .settled(&self.manager, settled, Category::Dapp, None)
+            .settled(&self.manager, settled, Category::Dapp, None, None)
             .await?;
         Ok(())
     }
@@ -0,0 +1,512 @@
+//! Typed client for the EIP-155 methods of [`EipMethod`].
+//!
+//! https://docs.walletconnect.com/advanced/multichain/rpc-reference/ethereum-rpc
+
+use {
+    crate::{ClientSession, Result},
+    alloy_primitives::{Address, Bytes, B256, U256, U64},
+    monedero_domain::namespaces::{AlloyChain, ChainId, EipMethod, Method},
+    serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer},
+    serde_json::{json, Value},
+    std::collections::BTreeMap,
+};
+
+/// Transaction object of `eth_sendTransaction` and `eth_signTransaction`.
+///
+/// Quantities are sent as hex, fields left out are filled in by the wallet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionRequest {
+    pub from: Address,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub to: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub value: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub data: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_priority_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub nonce: Option<U256>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedDataDomain {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub chain_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub verifying_contract: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub salt: Option<B256>,
+}
+
+/// EIP-712 typed data, `message` is checked against `types` by the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    pub primary_type: String,
+    pub domain: TypedDataDomain,
+    pub message: Value,
+}
+
+/// Native currency of `wallet_addEthereumChain`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Chain definition of `wallet_addEthereumChain`, see
+/// [EIP-3085](https://eips.ethereum.org/EIPS/eip-3085).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddEthereumChain {
+    pub chain_id: U64,
+    pub chain_name: String,
+    pub native_currency: NativeCurrency,
+    pub rpc_urls: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub block_explorer_urls: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub icon_urls: Vec<String>,
+}
+
+/// ERC-20 token of `wallet_watchAsset`, see
+/// [EIP-747](https://eips.ethereum.org/EIPS/eip-747).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchAsset {
+    pub address: Address,
+    pub symbol: String,
+    pub decimals: u8,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub image: Option<String>,
+}
+
+/// Wallet capabilities per hex chain id, e.g. `atomicBatch` or
+/// `paymasterService`.
+pub type Capabilities = BTreeMap<String, BTreeMap<String, Value>>;
+
+/// One call of a `wallet_sendCalls` batch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Call {
+    pub to: Address,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub data: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub value: Option<U256>,
+}
+
+/// Version of [EIP-5792](https://eips.ethereum.org/EIPS/eip-5792) sent by
+/// [`SendCalls::new`].
+pub const SEND_CALLS_VERSION: &str = "2.0.0";
+
+/// Batch of `wallet_sendCalls`, see [EIP-5792](https://eips.ethereum.org/EIPS/eip-5792).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendCalls {
+    pub version: String,
+    /// Batch id chosen by the dapp, the wallet picks one if left out
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub id: Option<String>,
+    pub from: Address,
+    pub calls: Vec<Call>,
+    /// The wallet must run the calls atomically or reject the batch
+    #[serde(default)]
+    pub atomic_required: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub capabilities: BTreeMap<String, Value>,
+}
+
+impl SendCalls {
+    pub fn new(from: Address, calls: Vec<Call>) -> Self {
+        Self {
+            version: String::from(SEND_CALLS_VERSION),
+            id: None,
+            from,
+            calls,
+            atomic_required: false,
+            capabilities: BTreeMap::new(),
+        }
+    }
+}
+
+/// Status of a `wallet_sendCalls` batch.
+///
+/// Sent as the numeric code of the final spec. The `PENDING` and
+/// `CONFIRMED` strings of earlier drafts are still understood.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallsStatus {
+    /// 1xx, not on chain yet
+    Pending,
+    /// 2xx, included without reverts
+    Confirmed,
+    /// 4xx, not included and not retried by the wallet
+    Failed,
+    /// 5xx, reverted completely
+    Reverted,
+    /// 6xx, some calls reverted
+    PartiallyReverted,
+}
+
+impl CallsStatus {
+    pub const fn code(self) -> u16 {
+        match self {
+            Self::Pending => 100,
+            Self::Confirmed => 200,
+            Self::Failed => 400,
+            Self::Reverted => 500,
+            Self::PartiallyReverted => 600,
+        }
+    }
+
+    pub const fn from_code(code: u16) -> Option<Self> {
+        let status = match code {
+            100..=199 => Self::Pending,
+            200..=299 => Self::Confirmed,
+            400..=499 => Self::Failed,
+            500..=599 => Self::Reverted,
+            600..=699 => Self::PartiallyReverted,
+            _ => return None,
+        };
+        Some(status)
+    }
+}
+
+impl Serialize for CallsStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for CallsStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Wire {
+            Code(u16),
+            Draft(String),
+        }
+        match Wire::deserialize(deserializer)? {
+            Wire::Code(code) => Self::from_code(code)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown status {code}"))),
+            Wire::Draft(status) => match status.as_str() {
+                "PENDING" => Ok(Self::Pending),
+                "CONFIRMED" => Ok(Self::Confirmed),
+                _ => Err(serde::de::Error::custom(format!("unknown status {status}"))),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallReceipt {
+    #[serde(default)]
+    pub logs: Vec<Value>,
+    pub status: U64,
+    pub block_hash: B256,
+    pub block_number: U64,
+    pub gas_used: U64,
+    pub transaction_hash: B256,
+}
+
+/// Result of `wallet_getCallsStatus`, receipts are there once confirmed.
+///
+/// Wallets on earlier drafts of EIP-5792 only send `status` and `receipts`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallsStatusResponse {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub chain_id: Option<U64>,
+    pub status: CallsStatus,
+    /// Whether the wallet ran the calls atomically
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub atomic: Option<bool>,
+    #[serde(default)]
+    pub receipts: Vec<CallReceipt>,
+}
+
+/// `wallet_sendCalls` answers with `{ id, capabilities }`, wallets on earlier
+/// drafts with the bare id.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CallsId {
+    Id(String),
+    Object { id: String },
+}
+
+/// Requests to the wallet on one EIP-155 chain, see [`ClientSession::eip155`].
+///
+/// Every request fails with [`crate::Error::MethodNotSettled`] unless the
+/// session namespaces, as last updated by the wallet, allow the method on
+/// this chain.
+pub struct Eip155Client<'a> {
+    session: &'a ClientSession,
+    chain: AlloyChain,
+}
+
+impl<'a> Eip155Client<'a> {
+    pub(crate) const fn new(session: &'a ClientSession, chain: AlloyChain) -> Self {
+        Self { session, chain }
+    }
+
+    async fn send<R: DeserializeOwned>(&self, method: EipMethod, params: Value) -> Result<R> {
+        self.session
+            .chain_request(ChainId::EIP155(self.chain), Method::EIP155(method), params)
+            .await
+    }
+
+    /// `personal_sign`, returns the 65 byte signature.
+    pub async fn personal_sign(
+        &self,
+        message: impl Into<Bytes>,
+        address: Address,
+    ) -> Result<Bytes> {
+        let params = json!([message.into(), address]);
+        self.send(EipMethod::PersonalSign, params).await
+    }
+
+    /// `eth_sign`, note the address comes first unlike `personal_sign`.
+    pub async fn sign(&self, address: Address, message: impl Into<Bytes>) -> Result<Bytes> {
+        let params = json!([address, message.into()]);
+        self.send(EipMethod::Sign, params).await
+    }
+
+    /// `eth_signTypedData`
+    pub async fn sign_typed_data(&self, address: Address, data: &TypedData) -> Result<Bytes> {
+        let params = json!([address, data]);
+        self.send(EipMethod::SignTypedData, params).await
+    }
+
+    /// `eth_signTypedData_v4`, the typed data goes as JSON string.
+    pub async fn sign_typed_data_v4(&self, address: Address, data: &TypedData) -> Result<Bytes> {
+        let params = json!([address, serde_json::to_string(data)?]);
+        self.send(EipMethod::SignTypedDataV4, params).await
+    }
+
+    /// `eth_signTransaction`, returns the signed raw transaction.
+    pub async fn sign_transaction(&self, tx: &TransactionRequest) -> Result<Bytes> {
+        self.send(EipMethod::SignTransaction, json!([tx])).await
+    }
+
+    /// `eth_sendTransaction`, returns the transaction hash.
+    pub async fn send_transaction(&self, tx: &TransactionRequest) -> Result<B256> {
+        self.send(EipMethod::SendTransaction, json!([tx])).await
+    }
+
+    /// `eth_signTypedData_v3`, same params as v4 for older wallets.
+    pub async fn sign_typed_data_v3(&self, address: Address, data: &TypedData) -> Result<Bytes> {
+        let params = json!([address, serde_json::to_string(data)?]);
+        self.send(EipMethod::SignTypedDataV3, params).await
+    }
+
+    /// `eth_accounts`
+    pub async fn accounts(&self) -> Result<Vec<Address>> {
+        self.send(EipMethod::Accounts, json!([])).await
+    }
+
+    /// `eth_requestAccounts`
+    pub async fn request_accounts(&self) -> Result<Vec<Address>> {
+        self.send(EipMethod::RequestAccounts, json!([])).await
+    }
+
+    /// `eth_chainId` as the wallet sees it.
+    pub async fn chain(&self) -> Result<u64> {
+        let id: U64 = self.send(EipMethod::ChainId, json!([])).await?;
+        Ok(id.to())
+    }
+
+    /// `wallet_switchEthereumChain` from this chain to `chain`.
+    pub async fn switch_chain(&self, chain: AlloyChain) -> Result<()> {
+        let params = json!([{ "chainId": U64::from(chain.id()) }]);
+        let _: Value = self.send(EipMethod::SwitchEthereumChain, params).await?;
+        Ok(())
+    }
+
+    /// `wallet_addEthereumChain`
+    pub async fn add_chain(&self, chain: &AddEthereumChain) -> Result<()> {
+        let _: Value = self
+            .send(EipMethod::AddEthereumChain, json!([chain]))
+            .await?;
+        Ok(())
+    }
+
+    /// `wallet_watchAsset` of an ERC-20 token, true if the user added it.
+    pub async fn watch_asset(&self, asset: &WatchAsset) -> Result<bool> {
+        let params = json!({ "type": "ERC20", "options": asset });
+        self.send(EipMethod::WatchAsset, params).await
+    }
+
+    /// `wallet_getCapabilities` of `address`.
+    pub async fn capabilities(&self, address: Address) -> Result<Capabilities> {
+        self.send(EipMethod::GetCapabilities, json!([address]))
+            .await
+    }
+
+    /// `wallet_sendCalls` on this chain, returns the batch id.
+    pub async fn send_calls(&self, calls: &SendCalls) -> Result<String> {
+        let mut call = serde_json::to_value(calls)?;
+        call["chainId"] = serde_json::to_value(U64::from(self.chain.id()))?;
+        let id: CallsId = self.send(EipMethod::SendCalls, json!([call])).await?;
+        match id {
+            CallsId::Id(id) | CallsId::Object { id } => Ok(id),
+        }
+    }
+
+    /// `wallet_getCallsStatus` of a batch id from [`Self::send_calls`].
+    pub async fn calls_status(&self, id: &str) -> Result<CallsStatusResponse> {
+        self.send(EipMethod::GetCallsStatus, json!([id])).await
+    }
+
+    /// `wallet_showCallsStatus`, asks the wallet to show the batch to the user.
+    pub async fn show_calls_status(&self, id: &str) -> Result<()> {
+        let _: Value = self.send(EipMethod::ShowCallsStatus, json!([id])).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloy_primitives::address};
+
+    #[test]
+    fn test_serde_transaction_request() -> anyhow::Result<()> {
+        let tx = TransactionRequest {
+            from: address!("BA5BA3955463ADcc7aa3E33bbdfb8A68e0933dD8"),
+            to: Some(Address::ZERO),
+            value: Some(U256::from(1_000_000_000u64)),
+            data: Some(Bytes::from_static(&[0xde, 0xad])),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&tx)?;
+        assert_eq!(
+            json!({
+                "from": "0xba5ba3955463adcc7aa3e33bbdfb8a68e0933dd8",
+                "to": "0x0000000000000000000000000000000000000000",
+                "value": "0x3b9aca00",
+                "data": "0xdead",
+            }),
+            json
+        );
+        assert_eq!(tx, serde_json::from_value(json)?);
+
+        let data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [{"name": "name", "type": "string"}],
+                "Mail": [{"name": "contents", "type": "string"}],
+            },
+            "primaryType": "Mail",
+            "domain": {"name": "Ether Mail", "chainId": 1},
+            "message": {"contents": "Hello, Bob!"},
+        }))?;
+        assert_eq!("string", data.types["Mail"][0].kind);
+        assert_eq!(Some(1), data.domain.chain_id);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_wallet_methods() -> anyhow::Result<()> {
+        let chain = AddEthereumChain {
+            chain_id: U64::from(AlloyChain::base_mainnet().id()),
+            chain_name: String::from("Base"),
+            native_currency: NativeCurrency {
+                name: String::from("Ether"),
+                symbol: String::from("ETH"),
+                decimals: 18,
+            },
+            rpc_urls: vec![String::from("https://mainnet.base.org")],
+            block_explorer_urls: vec![],
+            icon_urls: vec![],
+        };
+        assert_eq!(
+            json!({
+                "chainId": "0x2105",
+                "chainName": "Base",
+                "nativeCurrency": {"name": "Ether", "symbol": "ETH", "decimals": 18},
+                "rpcUrls": ["https://mainnet.base.org"],
+            }),
+            serde_json::to_value(&chain)?
+        );
+
+        let calls = SendCalls::new(Address::ZERO, vec![Call::default()]);
+        assert_eq!(
+            json!({
+                "version": "2.0.0",
+                "from": Address::ZERO,
+                "calls": [{"to": Address::ZERO}],
+                "atomicRequired": false,
+            }),
+            serde_json::to_value(&calls)?
+        );
+
+        let receipt = json!({
+            "logs": [],
+            "status": "0x1",
+            "blockHash": B256::ZERO,
+            "blockNumber": "0x10",
+            "gasUsed": "0x5208",
+            "transactionHash": B256::ZERO,
+        });
+        let status: CallsStatusResponse = serde_json::from_value(json!({
+            "version": "2.0.0",
+            "id": "0x01",
+            "chainId": "0x2105",
+            "status": 200,
+            "atomic": true,
+            "receipts": [receipt],
+        }))?;
+        assert_eq!(CallsStatus::Confirmed, status.status);
+        assert_eq!(Some(true), status.atomic);
+        assert_eq!(21000, status.receipts[0].gas_used.to::<u64>());
+        assert_eq!(json!(200), serde_json::to_value(status.status)?);
+        for (code, expected) in [
+            (100, CallsStatus::Pending),
+            (400, CallsStatus::Failed),
+            (500, CallsStatus::Reverted),
+            (600, CallsStatus::PartiallyReverted),
+        ] {
+            let status: CallsStatusResponse = serde_json::from_value(json!({"status": code}))?;
+            assert_eq!(expected, status.status);
+        }
+        assert!(serde_json::from_value::<CallsStatusResponse>(json!({"status": 300})).is_err());
+
+        // earlier drafts
+        let draft: CallsStatusResponse = serde_json::from_value(json!({
+            "status": "CONFIRMED",
+            "receipts": [receipt],
+        }))?;
+        assert_eq!(CallsStatus::Confirmed, draft.status);
+        assert_eq!(None, draft.atomic);
+        let pending: CallsStatusResponse = serde_json::from_value(json!({"status": "PENDING"}))?;
+        assert_eq!(CallsStatus::Pending, pending.status);
+        assert!(pending.receipts.is_empty());
+
+        let id: CallsId = serde_json::from_value(json!({"id": "0x01"}))?;
+        assert!(matches!(id, CallsId::Object { id } if id == "0x01"));
+        Ok(())
+    }
+}
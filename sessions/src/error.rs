@@ -59,8 +59,8 @@ pub enum Error {
     #[error("Got session settlement but I have no one to send this to!")]
     SessionSettlementNotFound,
 
-    #[error("RPC error {0:#?}")]
-    RpcError(serde_json::Value),
+    #[error("RPC error: {0}")]
+    RpcError(crate::rpc::PeerError),
 
     #[error("No pairing topic available")]
     NoPairingTopic,
@@ -88,4 +88,25 @@ pub enum Error {
 
     #[error("timeout occurred after {0}")]
     WaitError(u32),
+
+    #[error("pairing manager has shut down")]
+    ShutDown,
+
+    #[error("invalid request policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("invalid custom method: {0}")]
+    InvalidCustomMethod(String),
+
+    #[error("{0} on {1} is not part of the session namespaces")]
+    MethodNotSettled(
+        monedero_domain::namespaces::Method,
+        monedero_domain::namespaces::ChainId,
+    ),
+
+    #[error("invalid encoding: {0}")]
+    InvalidEncoding(String),
+
+    #[error("the connection options already have an auth token")]
+    AuthConflict,
 }
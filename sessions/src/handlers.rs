@@ -2,6 +2,7 @@ use {
     crate::{
         rpc::{
             Event,
+            PairDeleteRequest,
             RelayProtocol,
             ResponseParamsSuccess,
             RpcResponsePayload,
@@ -13,7 +14,7 @@ use {
         SocketEvent,
     },
     async_trait::async_trait,
-    monedero_domain::namespaces::Namespaces,
+    monedero_domain::{namespaces::Namespaces, Topic},
     serde_json::json,
 };
 
@@ -80,9 +81,20 @@ impl SessionHandler for NoopSessionHandler {
 pub struct NoopSessionDeleteHandler;
 impl SessionDeleteHandler for NoopSessionDeleteHandler {}
 
+/// Called when the peer deletes a session, see
+/// [`crate::ClientSession::on_delete`].
 #[async_trait]
 pub trait SessionDeleteHandler: Send + Sync + 'static {
-    async fn handle(&self, request: SessionDeleteRequest) {
-        tracing::info!("Session delete request {:#?}", request);
+    async fn handle(&self, topic: Topic, request: SessionDeleteRequest) {
+        tracing::info!("Session delete request {topic} {:#?}", request);
+    }
+}
+
+/// Called when the peer deletes the pairing, see
+/// [`crate::PairingManager::register_pair_delete_handler`].
+#[async_trait]
+pub trait PairDeleteHandler: Send + Sync + 'static {
+    async fn handle(&self, pairing_topic: Topic, request: PairDeleteRequest) {
+        tracing::info!("Pair delete request {pairing_topic} {:#?}", request);
     }
 }
@@ -0,0 +1,223 @@
+use {
+    crate::{
+        lifecycle::{Lifecycle, SessionLifecycleEvent},
+        tasks::Tasks,
+        wait,
+        ClientSession,
+        Error,
+        PairingManager,
+        Result,
+    },
+    monedero_domain::Topic,
+    std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tracing::{debug, info, warn},
+};
+
+/// How often a [`Heartbeat`] pings the peer and when it gives up on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeartbeatConfig {
+    pub interval: Duration,
+    /// A ping without an answer in this time counts as failed
+    pub timeout: Duration,
+    /// Consecutive failures before the peer is reported unreachable
+    pub failure_threshold: u32,
+    /// Delete the session or pairing after this many consecutive failures
+    pub teardown_after: Option<u32>,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(5),
+            failure_threshold: 3,
+            teardown_after: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Peer {
+    Session(ClientSession),
+    Pairing(Box<PairingManager>),
+}
+
+impl Peer {
+    fn topic(&self) -> Option<Topic> {
+        match self {
+            Self::Session(s) => Some(s.topic()),
+            Self::Pairing(p) => p.topic(),
+        }
+    }
+
+    fn lifecycle(&self) -> Lifecycle {
+        match self {
+            Self::Session(s) => s.lifecycle(),
+            Self::Pairing(p) => p.actors().lifecycle(),
+        }
+    }
+
+    fn tasks(&self) -> Tasks {
+        match self {
+            Self::Session(s) => s.tasks(),
+            Self::Pairing(p) => p.actors().tasks(),
+        }
+    }
+
+    async fn ping(&self) -> Result<bool> {
+        match self {
+            Self::Session(s) => s.ping().await,
+            Self::Pairing(p) => p.ping().await,
+        }
+    }
+
+    async fn teardown(&self, timeout: u32) {
+        match self {
+            Self::Session(s) => {
+                // the peer is unlikely to answer, don't wait for the full TTL
+                if wait::wait_until(timeout, s.delete()).await.is_err() {
+                    s.clear().await;
+                }
+            }
+            Self::Pairing(p) => {
+                if let Err(e) = p.delete().await {
+                    warn!("failed to delete pairing: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// Pings a session or pairing peer in the background.
+///
+/// Changes in reachability are reported on [`PairingManager::lifecycle`] as
+/// [`SessionLifecycleEvent::Unreachable`], [`SessionLifecycleEvent::Recovered`]
+/// and [`SessionLifecycleEvent::TornDown`]. Stops when dropped, when
+/// [`Heartbeat::stop`] is called, when the session or pairing is gone or when
+/// the manager shuts down.
+pub struct Heartbeat {
+    topic: Topic,
+    reachable: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Heartbeat {
+    #[allow(clippy::result_large_err)]
+    fn start(peer: Peer, config: HeartbeatConfig) -> Result<Self> {
+        let topic = peer.topic().ok_or(Error::NoPairingTopic)?;
+        let me = Self {
+            topic,
+            reachable: Arc::new(AtomicBool::new(true)),
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+        let tasks = peer.tasks();
+        tasks.spawn(beat(
+            peer,
+            config,
+            me.reachable.clone(),
+            me.stopped.clone(),
+        ));
+        Ok(me)
+    }
+
+    pub const fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// False while the peer is considered unreachable.
+    pub fn is_reachable(&self) -> bool {
+        self.reachable.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+async fn beat(
+    peer: Peer,
+    config: HeartbeatConfig,
+    reachable: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+) {
+    let Some(topic) = peer.topic() else {
+        return;
+    };
+    let lifecycle = peer.lifecycle();
+    let timeout = u32::try_from(config.timeout.as_millis()).unwrap_or(u32::MAX);
+    let mut failures: u32 = 0;
+    loop {
+        wait::sleep(config.interval).await;
+        if stopped.load(Ordering::Relaxed) {
+            debug!("heartbeat stopped for {topic}");
+            return;
+        }
+        match wait::wait_until(timeout, peer.ping()).await {
+            Ok(Ok(_)) => {
+                if !reachable.swap(true, Ordering::Relaxed) {
+                    info!("peer on {topic} is reachable again");
+                    lifecycle.emit(SessionLifecycleEvent::Recovered {
+                        topic: topic.clone(),
+                    });
+                }
+                failures = 0;
+                continue;
+            }
+            Ok(Err(
+                Error::NoClientSession(_)
+                | Error::NoPairingTopic
+                | Error::ShutDown
+                | Error::ActorSendError(_),
+            )) => {
+                debug!("heartbeat peer on {topic} is gone");
+                return;
+            }
+            Ok(Err(e)) => debug!("heartbeat ping failed on {topic}: {e}"),
+            Err(e) => debug!("heartbeat ping timed out on {topic}: {e}"),
+        }
+        failures = failures.saturating_add(1);
+        if failures == config.failure_threshold.max(1) {
+            warn!("peer on {topic} is unreachable after {failures} pings");
+            reachable.store(false, Ordering::Relaxed);
+            lifecycle.emit(SessionLifecycleEvent::Unreachable {
+                topic: topic.clone(),
+                failures,
+            });
+        }
+        if config.teardown_after.is_some_and(|n| failures >= n) {
+            warn!("tearing down {topic} after {failures} failed pings");
+            peer.teardown(timeout).await;
+            lifecycle.emit(SessionLifecycleEvent::TornDown { topic });
+            return;
+        }
+    }
+}
+
+impl ClientSession {
+    /// Ping the peer of this session every [`HeartbeatConfig::interval`].
+    #[allow(clippy::result_large_err)]
+    pub fn heartbeat(&self, config: HeartbeatConfig) -> Result<Heartbeat> {
+        Heartbeat::start(Peer::Session(self.clone()), config)
+    }
+}
+
+impl PairingManager {
+    /// Ping the peer of the current pairing every
+    /// [`HeartbeatConfig::interval`].
+    #[allow(clippy::result_large_err)]
+    pub fn heartbeat(&self, config: HeartbeatConfig) -> Result<Heartbeat> {
+        Heartbeat::start(Peer::Pairing(Box::new(self.clone())), config)
+    }
+}
@@ -1,36 +1,77 @@
 mod actors;
+mod custom;
 mod dapp;
+pub mod eip155;
 mod error;
 pub mod handlers;
+mod heartbeat;
+mod lifecycle;
+mod middleware;
 mod pair;
+mod policy;
+mod queues;
+mod rate_limit;
 mod relay;
 pub mod rpc;
 pub mod session;
+pub mod solana;
+mod tasks;
 mod transport;
 mod wait;
 mod wallet;
 
+#[cfg(all(feature = "mock", not(target_family = "wasm")))]
+pub use monedero_relay::LoopbackRelay;
 #[cfg(not(target_family = "wasm"))]
-pub use monedero_relay::MockRelay;
+pub use monedero_relay::{
+    MockFaults,
+    MockRelay,
+    MockRelayBuilder,
+    PublishFault,
+    TrafficKind,
+    TrafficRecord,
+    TrafficRecorder,
+};
 pub use {
     crate::{
         rpc::{SessionProposeRequest, SessionRequestRequest},
         session::ClientSession,
     },
     actors::{Actors, RegisteredComponents},
+    custom::{CustomHandler, CustomMethod},
     dapp::Dapp,
     error::Error,
     handlers::*,
+    heartbeat::{Heartbeat, HeartbeatConfig},
+    lifecycle::SessionLifecycleEvent,
+    middleware::{Flow, Middleware},
     monedero_domain as domain,
     monedero_relay::{
         auth_token,
         default_connection_opts,
+        default_user_agent,
         mock_connection_opts,
         ClientError,
+        ClientIdentity,
+        CloseFrame,
+        UserAgent,
+        AUTH_TTL,
         AUTH_URL,
+        CLOSE_POLICY_VIOLATION,
+        CLOSE_UNAUTHORIZED,
     },
     monedero_store::{Error as KvStorageError, KvStorage},
-    pair::{PairingManager, ReownBuilder},
+    pair::{
+        PairingManager,
+        ReconnectPolicy,
+        ReownBuilder,
+        ShutdownOptions,
+        AUTH_REFRESH_MARGIN,
+        RELAY_RETRY_AFTER,
+    },
+    policy::{ParamPredicate, ParamTest, Pattern, Policy, PolicyEngine, PolicyOutcome, PolicyRule},
+    queues::{QueueDepth, QueueLimits},
+    rate_limit::{RateLimit, RateLimits},
     rpc::{Metadata, SdkErrors},
     wallet::Wallet,
 };
@@ -41,19 +82,92 @@ use {
         fmt::{Display, Formatter},
         future::Future,
         pin::Pin,
-        sync::Once,
+        sync::{Arc, Once},
         task::{Context, Poll},
     },
     tokio::sync::oneshot,
     tracing_subscriber::{fmt::format::FmtSpan, EnvFilter},
 };
 
+/// Why the relay connection was lost.
+#[derive(Debug, Default, Clone)]
+pub struct DisconnectReason {
+    /// Websocket close code, if the relay sent a close frame
+    pub code: Option<u16>,
+    pub reason: Option<String>,
+    /// The transport or protocol error that ended the connection
+    pub error: Option<Arc<ClientError>>,
+}
+
+/// Errors are compared by kind only, their messages are ignored.
+impl PartialEq for DisconnectReason {
+    fn eq(&self, other: &Self) -> bool {
+        self.code == other.code
+            && self.reason == other.reason
+            && self.error.as_deref().map(std::mem::discriminant)
+                == other.error.as_deref().map(std::mem::discriminant)
+    }
+}
+
+impl Eq for DisconnectReason {}
+
+impl Display for DisconnectReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.code, &self.error) {
+            (Some(code), _) => write!(
+                f,
+                "closed with {code} {}",
+                self.reason.as_deref().unwrap_or_default()
+            ),
+            (None, Some(e)) => write!(f, "{e}"),
+            (None, None) => write!(f, "connection lost"),
+        }
+    }
+}
+
+impl From<CloseFrame<'static>> for DisconnectReason {
+    fn from(frame: CloseFrame<'static>) -> Self {
+        Self {
+            code: Some(frame.code),
+            reason: Some(frame.reason.into_owned()),
+            error: None,
+        }
+    }
+}
+
+impl From<ClientError> for DisconnectReason {
+    fn from(error: ClientError) -> Self {
+        Self {
+            code: None,
+            reason: None,
+            error: Some(Arc::new(error)),
+        }
+    }
+}
+
+impl DisconnectReason {
+    /// The relay rejected our credentials, reconnecting with the same token
+    /// would be rejected again.
+    pub fn is_auth_failure(&self) -> bool {
+        self.code.is_some_and(monedero_relay::is_auth_close)
+            || self.error.as_ref().is_some_and(|e| e.is_auth_failure())
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum SocketEvent {
     Connected,
     #[default]
     Disconnect,
-    ForceDisconnect,
+    ForceDisconnect(DisconnectReason),
+    /// Connected to a different relay endpoint than before, e.g. after a
+    /// failover.
+    ActiveRelay(String),
+    /// Reconnect attempt number, starting at 1
+    Reconnecting(u32),
+    /// The [`ReconnectPolicy`] ran out, no more attempts are made until
+    /// [`PairingManager::reconnect`] is called.
+    ReconnectGaveUp,
 }
 
 impl Display for SocketEvent {
@@ -65,8 +179,17 @@ impl Display for SocketEvent {
             Self::Disconnect => {
                 write!(f, "disconnected")
             }
-            Self::ForceDisconnect => {
-                write!(f, "force disconnect")
+            Self::ForceDisconnect(reason) => {
+                write!(f, "force disconnect: {reason}")
+            }
+            Self::ActiveRelay(address) => {
+                write!(f, "active relay {address}")
+            }
+            Self::Reconnecting(attempt) => {
+                write!(f, "reconnect attempt {attempt}")
+            }
+            Self::ReconnectGaveUp => {
+                write!(f, "gave up reconnecting")
             }
         }
     }
@@ -0,0 +1,216 @@
+use {
+    crate::{
+        rpc::{SessionEventRequest, SessionRequestRequest},
+        tasks::Tasks,
+        wait,
+        PairingManager,
+    },
+    dashmap::DashMap,
+    monedero_domain::{namespaces::Namespaces, SessionSettled, Topic},
+    std::{sync::Arc, time::Duration},
+    tokio::sync::{broadcast, Notify},
+    tracing::debug,
+};
+
+/// What happened to a session or pairing, as seen by this side.
+///
+/// Subscribe with [`PairingManager::lifecycle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionLifecycleEvent {
+    /// A session proposal was sent (dapp) or received (wallet)
+    Proposed {
+        pairing_topic: Topic,
+        required_namespaces: Namespaces,
+    },
+    /// A new or restored session is ready to use
+    Settled {
+        topic: Topic,
+        namespaces: Namespaces,
+        expiry: i64,
+    },
+    Updated {
+        topic: Topic,
+        namespaces: Namespaces,
+    },
+    Extended {
+        topic: Topic,
+        expiry: u64,
+    },
+    EventReceived {
+        topic: Topic,
+        event: SessionEventRequest,
+    },
+    RequestReceived {
+        topic: Topic,
+        request: SessionRequestRequest,
+    },
+    DeletedByPeer {
+        topic: Topic,
+    },
+    /// The session expiry passed without being extended
+    Expired {
+        topic: Topic,
+    },
+    PairingDeleted {
+        pairing_topic: Topic,
+    },
+    /// A peer request was rejected for exceeding
+    /// [`crate::RateLimits`]
+    RateLimited {
+        topic: Topic,
+        method: String,
+    },
+    /// The peer of a session or pairing missed
+    /// [`crate::HeartbeatConfig::failure_threshold`] pings in a row
+    Unreachable {
+        topic: Topic,
+        failures: u32,
+    },
+    /// The peer answered a heartbeat again after being unreachable
+    Recovered {
+        topic: Topic,
+    },
+    /// The session or pairing was deleted after
+    /// [`crate::HeartbeatConfig::teardown_after`] failed pings
+    TornDown {
+        topic: Topic,
+    },
+}
+
+const LIFECYCLE_CAPACITY: usize = 64;
+
+#[derive(Clone)]
+pub struct Lifecycle {
+    tx: broadcast::Sender<SessionLifecycleEvent>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(LIFECYCLE_CAPACITY);
+        Self { tx }
+    }
+}
+
+impl Lifecycle {
+    pub fn emit(&self, event: SessionLifecycleEvent) {
+        // no receivers is fine, nobody is listening
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionLifecycleEvent> {
+        self.tx.subscribe()
+    }
+}
+
+/// Session expiry in epoch seconds, moved forward on extend. Every change
+/// wakes the watchers, so a removed session stops its watcher right away.
+#[derive(Clone, Default)]
+pub struct Expiries {
+    expiries: Arc<DashMap<Topic, i64>>,
+    changed: Arc<Notify>,
+}
+
+impl Expiries {
+    pub fn get(&self, topic: &Topic) -> Option<i64> {
+        self.expiries.get(topic).map(|e| *e)
+    }
+
+    /// Returns the previous expiry of `topic`.
+    pub fn insert(&self, topic: Topic, expiry: i64) -> Option<i64> {
+        let previous = self.expiries.insert(topic, expiry);
+        self.changed.notify_waiters();
+        previous
+    }
+
+    /// Move the expiry of `topic`, if it is still watched.
+    pub fn extend(&self, topic: &Topic, expiry: i64) {
+        if let Some(mut current) = self.expiries.get_mut(topic) {
+            *current = expiry;
+        }
+        self.changed.notify_waiters();
+    }
+
+    pub fn remove(&self, topic: &Topic) {
+        self.expiries.remove(topic);
+        self.changed.notify_waiters();
+    }
+
+    pub fn clear(&self) {
+        self.expiries.clear();
+        self.changed.notify_waiters();
+    }
+}
+
+/// Sleep until the session on `topic` expires, then run `expire`. Returns
+/// without calling it once the session is removed.
+pub fn watch_expiry<F, Fut>(tasks: &Tasks, topic: Topic, expiries: Expiries, expire: F)
+where
+    F: FnOnce(Topic) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    tasks.spawn(async move {
+        loop {
+            let changed = expiries.changed.notified();
+            let Some(deadline) = expiries.get(&topic) else {
+                debug!("stop expiry watch for {topic}");
+                return;
+            };
+            let remaining = deadline.saturating_sub(chrono::Utc::now().timestamp());
+            if remaining <= 0 {
+                break;
+            }
+            // wake up at least every minute to follow the clock
+            let secs = u64::try_from(remaining).unwrap_or_default().min(60);
+            tokio::select! {
+                () = wait::sleep(Duration::from_secs(secs)) => {}
+                () = changed => {}
+            }
+        }
+        expire(topic).await;
+    });
+}
+
+impl From<&SessionSettled> for SessionLifecycleEvent {
+    fn from(value: &SessionSettled) -> Self {
+        Self::Settled {
+            topic: value.topic.clone(),
+            namespaces: value.namespaces.clone(),
+            expiry: value.expiry,
+        }
+    }
+}
+
+impl PairingManager {
+    /// Stream of session and pairing lifecycle events.
+    ///
+    /// Slow receivers lose the oldest events, see
+    /// [`broadcast::error::RecvError::Lagged`].
+    pub fn lifecycle(&self) -> broadcast::Receiver<SessionLifecycleEvent> {
+        self.actors().lifecycle().subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, tokio::sync::mpsc};
+
+    #[tokio::test]
+    async fn watch_expiry_stops_on_remove() {
+        let tasks = Tasks::default();
+        let expiries = Expiries::default();
+        let topic = Topic::generate();
+        expiries.insert(topic.clone(), chrono::Utc::now().timestamp() + 3600);
+        let (tx, mut expired) = mpsc::unbounded_channel();
+        watch_expiry(&tasks, topic.clone(), expiries.clone(), |topic| async move {
+            let _ = tx.send(topic);
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(1, tasks.running());
+
+        expiries.remove(&topic);
+        tokio::time::timeout(Duration::from_secs(1), tasks.wait())
+            .await
+            .expect("watcher did not stop");
+        assert!(expired.try_recv().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use {
+    crate::rpc::{ErrorParams, RequestParams, Response, ResponseParams, RpcRequest},
+    async_trait::async_trait,
+    monedero_domain::{MessageId, Topic},
+    std::sync::Arc,
+};
+
+/// What happens to a message after a [`Middleware`] has seen it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Flow<T> {
+    /// Pass the message, changed or not, to the next middleware
+    Next(T),
+    /// Stop here and answer with `error`. An outbound request fails with it
+    /// as if the peer had rejected it, an inbound request gets it as error
+    /// response, and an inbound response is replaced by it.
+    Stop(ErrorParams),
+}
+
+/// Sees every message of a [`crate::PairingManager`] in plain text: outbound
+/// requests before they are encrypted, inbound requests and responses right
+/// after they are decrypted.
+///
+/// Register with [`crate::ReownBuilder::middleware`], middleware runs in the
+/// order it was added.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn outbound_request(&self, _topic: &Topic, params: RequestParams) -> Flow<RequestParams> {
+        Flow::Next(params)
+    }
+
+    async fn inbound_request(&self, request: RpcRequest) -> Flow<RpcRequest> {
+        Flow::Next(request)
+    }
+
+    async fn inbound_response(&self, response: Response) -> Flow<Response> {
+        Flow::Next(response)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MiddlewareChain(Arc<Vec<Arc<dyn Middleware>>>);
+
+impl MiddlewareChain {
+    pub fn new(middleware: Vec<Arc<dyn Middleware>>) -> Self {
+        Self(Arc::new(middleware))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub async fn outbound_request(
+        &self,
+        topic: &Topic,
+        mut params: RequestParams,
+    ) -> Flow<RequestParams> {
+        for m in self.0.iter() {
+            match m.outbound_request(topic, params).await {
+                Flow::Next(next) => params = next,
+                stop @ Flow::Stop(_) => return stop,
+            }
+        }
+        Flow::Next(params)
+    }
+
+    pub async fn inbound_request(&self, mut request: RpcRequest) -> Flow<RpcRequest> {
+        for m in self.0.iter() {
+            match m.inbound_request(request).await {
+                Flow::Next(next) => request = next,
+                stop @ Flow::Stop(_) => return stop,
+            }
+        }
+        Flow::Next(request)
+    }
+
+    /// Always yields a response, a stop turns into an error response.
+    pub async fn inbound_response(&self, mut response: Response) -> Response {
+        let id = response.id;
+        for m in self.0.iter() {
+            match m.inbound_response(response).await {
+                Flow::Next(next) => response = next,
+                Flow::Stop(error) => return error_response(id, &error),
+            }
+        }
+        response
+    }
+}
+
+pub fn error_response(id: MessageId, error: &ErrorParams) -> Response {
+    let error = serde_json::to_value(error).unwrap_or_default();
+    Response::new(id, ResponseParams::Err(error))
+}
@@ -0,0 +1,76 @@
+use {
+    crate::{wait, Error, PairingManager, Result, AUTH_URL},
+    monedero_relay::{ClientIdentity, ConnectionOptions, SerializedAuthToken, AUTH_TTL},
+    std::{
+        sync::{
+            atomic::{AtomicI64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tracing::{info, warn},
+};
+
+/// Key of the persisted [`ClientIdentity`] in [`crate::KvStorage`]
+pub(super) const CLIENT_IDENTITY_KEY: &str = "relay-client-identity";
+
+/// How long before [`AUTH_TTL`] runs out a new token is minted.
+pub const AUTH_REFRESH_MARGIN: Duration = Duration::from_mins(5);
+
+/// Mints relay JWTs from a [`ClientIdentity`] and tracks how old the current
+/// one is.
+#[derive(Clone)]
+pub(super) struct RelayAuth {
+    identity: ClientIdentity,
+    minted_at: Arc<AtomicI64>,
+}
+
+impl RelayAuth {
+    pub(super) fn new(identity: ClientIdentity) -> Self {
+        Self {
+            identity,
+            minted_at: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    pub(super) const fn identity(&self) -> &ClientIdentity {
+        &self.identity
+    }
+
+    pub(super) fn is_stale(&self) -> bool {
+        let age = chrono::Utc::now().timestamp() - self.minted_at.load(Ordering::Relaxed);
+        let refresh_after = AUTH_TTL.saturating_sub(AUTH_REFRESH_MARGIN).as_secs();
+        u64::try_from(age).map_or(true, |age| age >= refresh_after)
+    }
+
+    /// A token for the relay at `audience`.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn mint(&self, audience: &str) -> Result<SerializedAuthToken> {
+        let token = self.identity.auth_token(AUTH_URL, audience).map_err(|e| {
+            warn!("failed to mint auth token {e}");
+            Error::JwtError
+        })?;
+        self.minted_at
+            .store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+        Ok(token)
+    }
+
+    /// Put a freshly minted token for the address of `opts` in `opts`.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn refresh(&self, opts: &mut ConnectionOptions) -> Result<()> {
+        opts.set_auth(self.mint(&opts.address)?);
+        Ok(())
+    }
+}
+
+/// Mint a new token and reconnect with it shortly before the current one
+/// expires, so a long-lived connection is not dropped by the relay.
+pub(super) async fn refresh_auth_periodically(mgr: PairingManager) {
+    loop {
+        wait::sleep(AUTH_TTL.saturating_sub(AUTH_REFRESH_MARGIN)).await;
+        info!("refreshing relay auth token");
+        if let Err(e) = mgr.refresh_auth().await {
+            warn!("failed to refresh relay auth token: {e}");
+        }
+    }
+}
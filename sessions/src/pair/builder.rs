@@ -1,16 +1,54 @@
 use {
-    crate::{auth_token, PairingManager, AUTH_URL},
+    super::{
+        auth::{RelayAuth, CLIENT_IDENTITY_KEY},
+        reconnect::ReconnectPolicy,
+        relays::{RelayPool, RELAY_RETRY_AFTER},
+    },
+    crate::{
+        actors::ActorConfig,
+        custom::CustomMethods,
+        middleware::MiddlewareChain,
+        rpc::CustomMetadata,
+        CustomMethod,
+        Middleware,
+        PairingManager,
+        QueueLimits,
+        RateLimits,
+        AUTH_URL,
+    },
     monedero_cipher::Cipher,
     monedero_domain::ProjectId,
-    monedero_relay::{ConnectionOptions, SerializedAuthToken},
+    monedero_relay::{
+        ClientIdentity,
+        ConnectionOptions,
+        SerializedAuthToken,
+        UserAgent,
+        RELAY_ADDRESS,
+    },
     monedero_store::KvStorage,
-    std::sync::Arc,
-    tracing::warn,
+    std::{sync::Arc, time::Duration},
+    tracing::{info, warn},
 };
 
+/// Configure and connect a [`PairingManager`].
+///
+/// Unless a token is given with [`ReownBuilder::auth`] or in the connection
+/// options, relay JWTs are minted from a [`ClientIdentity`] persisted in the
+/// store, so the relay sees the same client across restarts. Those tokens are refreshed, with a
+/// reconnect, [`crate::AUTH_REFRESH_MARGIN`] before they expire.
 pub struct ReownBuilder {
     connect_opts: Option<ConnectionOptions>,
     auth: Option<SerializedAuthToken>,
+    identity: Option<ClientIdentity>,
+    origin: Option<String>,
+    user_agent: Option<UserAgent>,
+    relays: Vec<String>,
+    relay_retry_after: Duration,
+    reconnect: ReconnectPolicy,
+    queues: QueueLimits,
+    rate_limits: RateLimits,
+    middleware: Vec<Arc<dyn Middleware>>,
+    custom: Vec<(&'static str, CustomMetadata)>,
     project_id: ProjectId,
     store: Option<KvStorage>,
 }
@@ -20,11 +58,26 @@ impl ReownBuilder {
         Self {
             connect_opts: None,
             auth: None,
+            identity: None,
+            origin: None,
+            user_agent: None,
+            relays: Vec::new(),
+            relay_retry_after: RELAY_RETRY_AFTER,
+            reconnect: ReconnectPolicy::default(),
+            queues: QueueLimits::default(),
+            rate_limits: RateLimits::default(),
+            middleware: Vec::new(),
+            custom: Vec::new(),
             project_id,
             store: None,
         }
     }
 
+    /// Connect with `opts`. A token in `opts` is used as given and not
+    /// refreshed, build fails with [`crate::Error::AuthConflict`] if
+    /// [`ReownBuilder::auth`] or [`ReownBuilder::identity`] is set as well.
+    /// Without one, see [`ConnectionOptions::without_auth`], the token comes
+    /// from the builder.
     #[must_use]
     pub fn connect_opts(mut self, opts: ConnectionOptions) -> Self {
         self.connect_opts = Some(opts);
@@ -37,28 +90,127 @@ impl ReownBuilder {
         self
     }
 
+    /// Use a fixed auth token. It is not refreshed.
     #[must_use]
     pub fn auth(mut self, auth: SerializedAuthToken) -> Self {
         self.auth = Some(auth);
         self
     }
 
-    pub async fn build(&self) -> crate::Result<PairingManager> {
-        let auth: SerializedAuthToken = self.auth.as_ref().map_or_else(
-            || {
-                if self.connect_opts.is_none() {
-                    warn!("using default auth URL {AUTH_URL}");
-                }
-                auth_token(AUTH_URL)
-            },
-            std::clone::Clone::clone,
-        );
+    /// Relay endpoints in order of preference. When one cannot be reached
+    /// the next is tried, on startup and on every reconnect. Defaults to the
+    /// address of the connection options.
+    #[must_use]
+    pub fn relays(mut self, addresses: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.relays = addresses.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How long a relay endpoint that failed goes behind the healthy ones.
+    /// After that it is tried in its configured place again, so a recovered
+    /// primary is picked on the next reconnect.
+    #[must_use]
+    pub const fn relay_retry_after(mut self, retry_after: Duration) -> Self {
+        self.relay_retry_after = retry_after;
+        self
+    }
+
+    /// How to reconnect when the relay drops the connection.
+    #[must_use]
+    pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Capacities of the inbound queues and actor mailboxes.
+    #[must_use]
+    pub fn queue_limits(mut self, limits: QueueLimits) -> Self {
+        self.queues = limits;
+        self
+    }
+
+    /// Limits on requests from peers, see [`RateLimits`].
+    #[must_use]
+    pub fn rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limits = limits;
+        self
+    }
+
+    /// Accept requests and responses of `M` on session topics.
+    #[must_use]
+    pub fn custom_method<M: CustomMethod>(mut self) -> Self {
+        self.custom.push((M::METHOD, CustomMetadata {
+            request: M::REQUEST,
+            response: M::RESPONSE,
+        }));
+        self
+    }
+
+    /// Add `middleware` after the middleware added so far.
+    #[must_use]
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
 
-        let opts: ConnectionOptions = self.connect_opts.as_ref().map_or_else(
-            || ConnectionOptions::new(self.project_id.clone(), auth.clone()),
-            std::clone::Clone::clone,
-        );
+    /// `Origin` sent to the relay, it must be in the project's allow-list if
+    /// the project has one.
+    #[must_use]
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    /// Identify as `user_agent` instead of
+    /// [`monedero_relay::default_user_agent`].
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: UserAgent) -> Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Use `identity` instead of the one persisted in the store.
+    #[must_use]
+    pub fn identity(mut self, identity: ClientIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// A token for options without one, with the [`RelayAuth`] that renews it
+    /// if it is minted from the client identity.
+    #[allow(clippy::result_large_err)]
+    fn mint_auth(
+        &self,
+        store: &KvStorage,
+        audience: &str,
+    ) -> crate::Result<(SerializedAuthToken, Option<RelayAuth>)> {
+        if let Some(auth) = &self.auth {
+            return Ok((auth.clone(), None));
+        }
+        if self.connect_opts.is_none() {
+            warn!("using default auth URL {AUTH_URL}");
+        }
+        let identity = match &self.identity {
+            Some(identity) => identity.clone(),
+            None => Self::load_identity(store)?,
+        };
+        let relay_auth = RelayAuth::new(identity);
+        Ok((relay_auth.mint(audience)?, Some(relay_auth)))
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn load_identity(store: &KvStorage) -> crate::Result<ClientIdentity> {
+        store.retain(CLIENT_IDENTITY_KEY);
+        if let Some(identity) = store.get::<ClientIdentity>(CLIENT_IDENTITY_KEY)? {
+            return Ok(identity);
+        }
+        let identity = ClientIdentity::generate();
+        info!("created new relay client identity {identity:?}");
+        store.set(CLIENT_IDENTITY_KEY, identity.clone())?;
+        Ok(identity)
+    }
 
+    pub async fn build(&self) -> crate::Result<PairingManager> {
         #[cfg(not(target_arch = "wasm32"))]
         let store = match self.store.as_ref() {
             Some(s) => s.clone(),
@@ -68,8 +220,57 @@ impl ReownBuilder {
         #[cfg(target_arch = "wasm32")]
         let store = KvStorage::new();
 
+        let (mut opts, relay_auth) = match &self.connect_opts {
+            Some(opts) if opts.has_auth() => {
+                if self.auth.is_some() || self.identity.is_some() {
+                    return Err(crate::Error::AuthConflict);
+                }
+                (opts.clone(), None)
+            }
+            Some(opts) => {
+                let (auth, relay_auth) = self.mint_auth(&store, &opts.address)?;
+                let mut opts = opts.clone();
+                opts.set_auth(auth);
+                (opts, relay_auth)
+            }
+            None => {
+                let (auth, relay_auth) = self.mint_auth(&store, RELAY_ADDRESS)?;
+                (ConnectionOptions::new(self.project_id.clone(), auth), relay_auth)
+            }
+        };
+        if let Some(origin) = &self.origin {
+            opts = opts.with_origin(origin.clone());
+        }
+        if let Some(user_agent) = &self.user_agent {
+            opts = opts.with_user_agent(user_agent.clone());
+        }
+
+        let relays = if self.relays.is_empty() {
+            RelayPool::new([opts.address.clone()], self.relay_retry_after)
+        } else {
+            RelayPool::new(self.relays.iter().cloned(), self.relay_retry_after)
+        };
+
+        let custom = CustomMethods::default();
+        for (method, metadata) in &self.custom {
+            custom.register(method, *metadata)?;
+        }
+
         let store = Arc::new(store);
         let cipher = Cipher::new(store, None)?;
-        PairingManager::init(opts, cipher).await
+        PairingManager::init(
+            opts,
+            relay_auth,
+            relays,
+            self.reconnect.clone(),
+            cipher,
+            ActorConfig {
+                queues: self.queues.clone(),
+                rate_limits: self.rate_limits.clone(),
+                middleware: MiddlewareChain::new(self.middleware.clone()),
+                custom,
+            },
+        )
+        .await
     }
 }
@@ -1,5 +1,7 @@
 use {
     crate::{
+        actors::ClearPairing,
+        lifecycle::SessionLifecycleEvent,
         rpc::{
             PairDeleteRequest,
             PairExtendRequest,
@@ -7,12 +9,11 @@ use {
             ResponseParamsSuccess,
             RpcResponsePayload,
         },
-        spawn_task,
         PairingManager,
         Topic,
     },
     std::time::Duration,
-    tracing::info,
+    tracing::{info, warn},
     xtra::prelude::*,
 };
 
@@ -46,15 +47,18 @@ impl Handler<PairDeleteRequest> for PairingManager {
 
     async fn handle(
         &mut self,
-        _message: PairDeleteRequest,
+        message: PairDeleteRequest,
         _ctx: &mut Context<Self>,
     ) -> Self::Return {
         if let Some(pairing) = self.ciphers.pairing() {
             let mgr = self.clone();
-            spawn_task(async move {
+            self.actors.tasks().spawn_flushed(async move {
                 // Give time some time to respond to delete request
                 tokio::time::sleep(Duration::from_secs(1)).await;
-                mgr.cleanup(pairing.topic).await;
+                info!("peer deleted pairing: {}", message.message);
+                mgr.cleanup(pairing.topic.clone()).await;
+                mgr.notify_pair_delete_handlers(pairing.topic, message)
+                    .await;
             });
         }
         RpcResponsePayload::Success(ResponseParamsSuccess::PairPing(true))
@@ -64,11 +68,21 @@ impl Handler<PairDeleteRequest> for PairingManager {
 impl PairingManager {
     pub(super) async fn cleanup(&self, pairing_topic: Topic) {
         info!("deleting pairing topic {pairing_topic}");
-        let _ = self.transport.unsubscribe(pairing_topic).await;
+        // closes the sessions of this pairing
+        if let Err(e) = self.actors.session().send(ClearPairing).await {
+            warn!("failed to clear sessions: {e}");
+        }
+        let _ = self.transport.unsubscribe(pairing_topic.clone()).await;
+        let limiter = self.actors.limiter();
+        limiter.forget(&pairing_topic);
         let topics = self.ciphers.subscriptions();
         for t in topics {
+            limiter.forget(&t);
             let _ = self.relay.unsubscribe(t).await;
         }
         let _ = self.ciphers.set_pairing(None);
+        self.actors
+            .lifecycle()
+            .emit(SessionLifecycleEvent::PairingDeleted { pairing_topic });
     }
 }
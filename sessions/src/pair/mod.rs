@@ -1,49 +1,62 @@
+mod auth;
 mod builder;
 mod handlers;
 mod pairing;
+mod reconnect;
 mod registration;
-#[cfg(not(target_family = "wasm"))]
+mod relays;
+mod shutdown;
 mod socket_handler;
-#[cfg(target_family = "wasm")]
-mod socket_handler_wasm;
-pub use builder::ReownBuilder;
-#[cfg(not(target_family = "wasm"))]
 use socket_handler::handle_socket;
-#[cfg(target_family = "wasm")]
-use socket_handler_wasm::handle_socket;
 use {
     crate::{
-        actors::Actors,
-        relay::RelayHandler,
+        actors::{ActorConfig, Actors},
+        queues::{QueueDepth, SharedInboundStats},
+        relay::{InboundActors, RelayHandler},
         rpc::{PairDeleteRequest, PairExtendRequest, PairPingRequest, RequestParams},
-        spawn_task,
         transport::TopicTransport,
         wait,
         Error,
+        PairDeleteHandler,
         Result,
         SocketEvent,
         SocketListener,
     },
+    auth::RelayAuth,
     monedero_cipher::Cipher,
     monedero_domain::{namespaces::Namespaces, Pairing, SessionSettled, SubscriptionId, Topic},
-    monedero_relay::{Client, ConnectionOptions},
+    monedero_relay::{Client, ClientId, ConnectionOptions},
+    relays::RelayPool,
     serde::de::DeserializeOwned,
     std::{
         fmt::{Debug, Formatter},
-        sync::Arc,
+        sync::{Arc, PoisonError, RwLock},
     },
     tokio::sync::mpsc,
     tracing::{info, warn},
 };
+pub use {
+    auth::AUTH_REFRESH_MARGIN,
+    builder::ReownBuilder,
+    reconnect::ReconnectPolicy,
+    relays::RELAY_RETRY_AFTER,
+    shutdown::ShutdownOptions,
+};
 
 #[derive(Clone, xtra::Actor)]
 pub struct PairingManager {
     relay: Client,
-    opts: ConnectionOptions,
+    opts: Arc<RwLock<ConnectionOptions>>,
+    auth: Option<RelayAuth>,
+    relays: RelayPool,
+    reconnect: Arc<ReconnectPolicy>,
+    socket_tx: mpsc::UnboundedSender<SocketEvent>,
     ciphers: Cipher,
     transport: TopicTransport,
     actors: Actors,
     pub(super) socket_listeners: Arc<tokio::sync::Mutex<Vec<Box<dyn SocketListener>>>>,
+    pair_delete_handlers: Arc<tokio::sync::Mutex<Vec<Box<dyn PairDeleteHandler>>>>,
+    inbound: SharedInboundStats,
 }
 
 impl Debug for PairingManager {
@@ -51,39 +64,67 @@ impl Debug for PairingManager {
         let t: String = self
             .topic()
             .map_or_else(|| String::from("none"), |t| crate::shorten_topic(&t));
-        write!(f, "pairing={} projectId={}", t, self.opts.project_id)
+        write!(
+            f,
+            "pairing={} projectId={}",
+            t,
+            self.connection_opts().project_id
+        )
     }
 }
 
 impl PairingManager {
-    async fn init(opts: ConnectionOptions, ciphers: Cipher) -> Result<Self> {
-        let actors = Actors::init(ciphers.clone());
+    async fn init(
+        opts: ConnectionOptions,
+        auth: Option<RelayAuth>,
+        relays: RelayPool,
+        reconnect: ReconnectPolicy,
+        ciphers: Cipher,
+        config: ActorConfig,
+    ) -> Result<Self> {
+        let actors = Actors::init(ciphers.clone(), &config);
         let (socket_tx, socket_rx) = mpsc::unbounded_channel::<SocketEvent>();
+        let inbound = SharedInboundStats::default();
         let handler = RelayHandler::new(
             ciphers.clone(),
-            actors.request(),
-            actors.response(),
-            socket_tx,
+            InboundActors {
+                request: actors.request(),
+                response: actors.response(),
+                transport: actors.transport(),
+            },
+            socket_tx.clone(),
+            &config,
+            inbound.clone(),
+            &actors.tasks(),
         );
         let relay = Client::new(handler);
         actors.register_client(relay.clone()).await?;
-        relay.connect(&opts).await?;
 
         let transport = TopicTransport::new(actors.transport());
 
         let mgr = Self {
             relay,
-            opts,
+            opts: Arc::new(RwLock::new(opts)),
+            auth,
+            relays,
+            reconnect: Arc::new(reconnect),
+            socket_tx,
             ciphers,
             transport,
             actors: actors.clone(),
             socket_listeners: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            pair_delete_handlers: Arc::new(tokio::sync::Mutex::new(Vec::new())),
+            inbound,
         };
         actors.request().send(mgr.clone()).await?;
         let socket_handler = mgr.clone();
-        spawn_task(handle_socket(socket_handler, socket_rx));
+        let tasks = actors.tasks();
+        tasks.spawn(handle_socket(socket_handler, socket_rx));
         mgr.open_socket().await?;
         mgr.restore_saved_pairing().await?;
+        if mgr.auth.is_some() {
+            tasks.spawn(auth::refresh_auth_periodically(mgr.clone()));
+        }
         Ok(mgr)
     }
 
@@ -92,6 +133,34 @@ impl PairingManager {
         l.push(Box::new(listener));
     }
 
+    /// Call `handler` when the peer deletes the pairing.
+    pub async fn register_pair_delete_handler<T: PairDeleteHandler>(&self, handler: T) {
+        let mut h = self.pair_delete_handlers.lock().await;
+        h.push(Box::new(handler));
+    }
+
+    pub(super) async fn notify_pair_delete_handlers(
+        &self,
+        pairing_topic: Topic,
+        request: PairDeleteRequest,
+    ) {
+        let h = self.pair_delete_handlers.lock().await;
+        for handler in h.iter() {
+            handler.handle(pairing_topic.clone(), request.clone()).await;
+        }
+    }
+
+    pub(super) async fn notify_socket_listeners(&self, event: SocketEvent) {
+        let l = self.socket_listeners.lock().await;
+        for listener in l.iter() {
+            listener.handle_socket_event(event.clone()).await;
+        }
+    }
+
+    pub(super) fn reconnect_policy(&self) -> &ReconnectPolicy {
+        &self.reconnect
+    }
+
     pub(crate) async fn resubscribe(&self) -> Result<()> {
         self.pairing().ok_or(Error::NoPairingTopic)?;
         let topics = self.ciphers.subscriptions();
@@ -124,6 +193,70 @@ impl PairingManager {
         })
     }
 
+    /// The relay client ID, when the JWT is minted from a persisted
+    /// [`monedero_relay::ClientIdentity`].
+    pub fn client_id(&self) -> Option<ClientId> {
+        self.auth.as_ref().map(|a| a.identity().client_id())
+    }
+
+    fn connection_opts(&self) -> ConnectionOptions {
+        self.opts
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Connection options with a valid auth token, minting a new one if the
+    /// current token is close to expiry.
+    #[allow(clippy::result_large_err)]
+    fn fresh_connection_opts(&self) -> Result<ConnectionOptions> {
+        let mut opts = self.opts.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(auth) = self.auth.as_ref().filter(|a| a.is_stale()) {
+            auth.refresh(&mut opts)?;
+        }
+        Ok(opts.clone())
+    }
+
+    /// Mint a token for the next connection. Returns false if the token was
+    /// given by the application and cannot be renewed.
+    #[allow(clippy::result_large_err)]
+    pub(super) fn renew_auth_token(&self) -> Result<bool> {
+        let Some(auth) = &self.auth else {
+            return Ok(false);
+        };
+        auth.refresh(&mut self.opts.write().unwrap_or_else(PoisonError::into_inner))?;
+        Ok(true)
+    }
+
+    /// Mint a new auth token and reconnect with it.
+    pub async fn refresh_auth(&self) -> Result<()> {
+        if !self.renew_auth_token()? {
+            return Ok(());
+        }
+        self.reconnect().await
+    }
+
+    /// Drop the current connection, connect again and resubscribe. Use it to
+    /// retry after [`SocketEvent::ReconnectGaveUp`].
+    pub async fn reconnect(&self) -> Result<()> {
+        self.disconnect_socket().await?;
+        self.open_socket().await?;
+        match self.resubscribe().await {
+            Ok(()) | Err(Error::NoPairingTopic) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Messages waiting in the inbound channels and actor mailboxes.
+    pub fn queue_depth(&self) -> QueueDepth {
+        QueueDepth {
+            inbound_requests: self.inbound.requests(),
+            inbound_responses: self.inbound.responses(),
+            shed: self.inbound.shed(),
+            ..self.actors.queue_depth()
+        }
+    }
+
     pub fn ciphers(&self) -> Cipher {
         self.ciphers.clone()
     }
@@ -214,14 +347,42 @@ impl PairingManager {
         self.transport.publish_request(topic, params).await
     }
 
-    pub async fn shutdown(&self) -> Result<()> {
-        self.disconnect_socket().await
+    /// The relay endpoint of the current connection.
+    pub fn active_relay(&self) -> Option<String> {
+        self.relays.active()
     }
 
+    /// Connect to the healthiest relay endpoint, falling back to the next one
+    /// in order when a connection fails.
     #[tracing::instrument(level = "info")]
     pub async fn open_socket(&self) -> Result<()> {
-        self.relay.connect(&self.opts).await?;
-        Ok(())
+        if self.is_shut_down() {
+            return Err(Error::ShutDown);
+        }
+        let opts = self.fresh_connection_opts()?;
+        let mut result = Ok(());
+        for address in self.relays.candidates() {
+            let mut attempt = opts.clone().with_address(address.clone());
+            // a token is only accepted by the relay it was minted for
+            if let Some(auth) = self.auth.as_ref().filter(|_| address != opts.address) {
+                auth.refresh(&mut attempt)?;
+            }
+            result = self.relay.connect(&attempt).await;
+            match &result {
+                Ok(()) => {
+                    if self.relays.connected(&address) {
+                        info!("active relay {address}");
+                        let _ = self.socket_tx.send(SocketEvent::ActiveRelay(address));
+                    }
+                    break;
+                }
+                Err(e) => {
+                    warn!("failed to connect to relay {address}: {e}");
+                    self.relays.failed(&address);
+                }
+            }
+        }
+        Ok(result?)
     }
 
     pub async fn disconnect_socket(&self) -> Result<()> {
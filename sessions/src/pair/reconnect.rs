@@ -0,0 +1,111 @@
+use {
+    crate::{wait, DisconnectReason, Error, PairingManager, SocketEvent},
+    backoff::{backoff::Backoff, ExponentialBackoff, ExponentialBackoffBuilder},
+    std::time::Duration,
+    tracing::{debug, info, warn},
+};
+
+/// How [`PairingManager`] reconnects after the relay drops the connection.
+///
+/// Attempts back off exponentially from `initial_interval` up to
+/// `max_interval`. Once `max_elapsed_time` has passed without a connection
+/// it gives up and reports [`SocketEvent::ReconnectGaveUp`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Wait before the first attempt
+    pub initial_delay: Duration,
+    pub initial_interval: Duration,
+    pub max_interval: Duration,
+    pub multiplier: f64,
+    /// `None` retries forever
+    pub max_elapsed_time: Option<Duration>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(3),
+            initial_interval: Duration::from_secs(3),
+            max_interval: Duration::from_mins(1),
+            multiplier: backoff::default::MULTIPLIER,
+            max_elapsed_time: Some(Duration::from_mins(1)),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Default intervals, but never give up.
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self {
+            max_elapsed_time: None,
+            ..Self::default()
+        }
+    }
+
+    fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoffBuilder::new()
+            .with_initial_interval(self.initial_interval)
+            .with_max_interval(self.max_interval)
+            .with_multiplier(self.multiplier)
+            .with_max_elapsed_time(self.max_elapsed_time)
+            .build()
+    }
+}
+
+/// Reconnect after the relay dropped the connection, unless it rejected our
+/// credentials and there is no way to get new ones.
+pub(super) async fn handle_disconnect(mgr: PairingManager, reason: DisconnectReason) {
+    if mgr.is_shut_down() {
+        debug!("disconnected after shutdown");
+        return;
+    }
+    if reason.is_auth_failure() {
+        match mgr.renew_auth_token() {
+            Ok(true) => info!("relay rejected the auth token, minted a new one"),
+            Ok(false) => {
+                warn!("relay rejected the auth token ({reason}), not reconnecting");
+                mgr.notify_socket_listeners(SocketEvent::ReconnectGaveUp)
+                    .await;
+                return;
+            }
+            Err(e) => {
+                warn!("failed to mint auth token: {e}");
+                mgr.notify_socket_listeners(SocketEvent::ReconnectGaveUp)
+                    .await;
+                return;
+            }
+        }
+    }
+    reconnect_with_backoff(mgr).await;
+}
+
+async fn reconnect_with_backoff(mgr: PairingManager) {
+    let policy = mgr.reconnect_policy().clone();
+    info!("reconnecting");
+    wait::sleep(policy.initial_delay).await;
+    let mut backoff = policy.backoff();
+    let mut attempt: u32 = 0;
+    loop {
+        attempt = attempt.saturating_add(1);
+        info!("attempting reconnect {attempt}");
+        mgr.notify_socket_listeners(SocketEvent::Reconnecting(attempt))
+            .await;
+        let Err(e) = mgr.open_socket().await else {
+            break;
+        };
+        let rejected = matches!(&e, Error::ConnectError(e) if e.is_auth_failure());
+        let Some(delay) = backoff.next_backoff().filter(|_| !rejected) else {
+            warn!("failed to reconnect after {attempt} attempts: {e}");
+            mgr.notify_socket_listeners(SocketEvent::ReconnectGaveUp)
+                .await;
+            return;
+        };
+        debug!("reconnect failed: {e}, retry in {delay:?}");
+        wait::sleep(delay).await;
+    }
+    debug!("re-subsribing");
+    if let Err(e) = mgr.resubscribe().await {
+        warn!("failed to resubscribe! {e}");
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+/// Default of [`crate::ReownBuilder::relay_retry_after`].
+pub const RELAY_RETRY_AFTER: Duration = Duration::from_mins(1);
+
+#[derive(Debug, Clone)]
+struct RelayEndpoint {
+    address: String,
+    /// Connection attempts that failed since the last successful one
+    failures: u32,
+    /// Unix time in milliseconds of the last failed attempt
+    failed_at: i64,
+}
+
+/// Ordered relay endpoints with their health.
+///
+/// Endpoints are tried in the configured order, except that those which
+/// failed within `retry_after` go to the back, fewest failures first. Once
+/// `retry_after` has passed they are back in their configured place, so a
+/// recovered primary is picked again on the next reconnect that comes later.
+#[derive(Clone)]
+pub(super) struct RelayPool {
+    endpoints: Arc<RwLock<Vec<RelayEndpoint>>>,
+    active: Arc<RwLock<Option<String>>>,
+    retry_after: Duration,
+}
+
+impl Debug for RelayPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[relays {:?} active={:?}]",
+            self.addresses(),
+            self.active()
+        )
+    }
+}
+
+impl RelayPool {
+    pub(super) fn new(addresses: impl IntoIterator<Item = String>, retry_after: Duration) -> Self {
+        let mut endpoints: Vec<RelayEndpoint> = Vec::new();
+        for address in addresses {
+            if !endpoints.iter().any(|e| e.address == address) {
+                endpoints.push(RelayEndpoint {
+                    address,
+                    failures: 0,
+                    failed_at: 0,
+                });
+            }
+        }
+        Self {
+            endpoints: Arc::new(RwLock::new(endpoints)),
+            active: Arc::new(RwLock::new(None)),
+            retry_after,
+        }
+    }
+
+    pub(super) fn addresses(&self) -> Vec<String> {
+        self.endpoints
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|e| e.address.clone())
+            .collect()
+    }
+
+    /// Endpoints in the order they should be tried.
+    pub(super) fn candidates(&self) -> Vec<String> {
+        let mut endpoints = self
+            .endpoints
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let now = chrono::Utc::now().timestamp_millis();
+        let retry_after = i64::try_from(self.retry_after.as_millis()).unwrap_or(i64::MAX);
+        // stable, so equally healthy endpoints keep their configured order
+        endpoints.sort_by_key(|e| {
+            if now.saturating_sub(e.failed_at) < retry_after {
+                e.failures
+            } else {
+                0
+            }
+        });
+        endpoints.into_iter().map(|e| e.address).collect()
+    }
+
+    pub(super) fn active(&self) -> Option<String> {
+        self.active
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn update(&self, address: &str, f: impl FnOnce(&mut RelayEndpoint)) {
+        let mut endpoints = self
+            .endpoints
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(endpoint) = endpoints.iter_mut().find(|e| e.address == address) {
+            f(endpoint);
+        }
+    }
+
+    pub(super) fn failed(&self, address: &str) {
+        self.update(address, |e| {
+            e.failures = e.failures.saturating_add(1);
+            e.failed_at = chrono::Utc::now().timestamp_millis();
+        });
+        let mut active = self.active.write().unwrap_or_else(PoisonError::into_inner);
+        if active.as_deref() == Some(address) {
+            *active = None;
+        }
+    }
+
+    /// Marks `address` healthy and active. Returns true if the active relay
+    /// changed.
+    pub(super) fn connected(&self, address: &str) -> bool {
+        self.update(address, |e| e.failures = 0);
+        let mut active = self.active.write().unwrap_or_else(PoisonError::into_inner);
+        if active.as_deref() == Some(address) {
+            return false;
+        }
+        *active = Some(String::from(address));
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn relay_pool_order() {
+        let pool = RelayPool::new(["a", "b", "a", "c"].map(String::from), RELAY_RETRY_AFTER);
+        assert_eq!(vec!["a", "b", "c"], pool.candidates());
+        assert!(pool.connected("a"));
+        assert!(!pool.connected("a"));
+
+        pool.failed("a");
+        assert_eq!(None, pool.active());
+        assert_eq!(vec!["b", "c", "a"], pool.candidates());
+        pool.failed("b");
+        pool.failed("a");
+        assert_eq!(vec!["c", "b", "a"], pool.candidates());
+
+        assert!(pool.connected("a"));
+        assert_eq!(Some(String::from("a")), pool.active());
+        assert_eq!(vec!["a", "c", "b"], pool.candidates());
+    }
+
+    #[test]
+    fn relay_pool_retry_after() {
+        let pool = RelayPool::new(["a", "b"].map(String::from), Duration::ZERO);
+        pool.failed("a");
+        assert!(pool.connected("b"));
+        // a is retried first again once retry_after has passed
+        assert_eq!(vec!["a", "b"], pool.candidates());
+    }
+}
@@ -0,0 +1,99 @@
+use {
+    crate::{
+        actors::ClearSession,
+        rpc::{RequestParams, SessionDeleteRequest},
+        wait,
+        PairingManager,
+        Result,
+    },
+    std::time::Duration,
+    tracing::{info, warn},
+};
+
+/// What [`PairingManager::shutdown_with`] does before it stops.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownOptions {
+    /// Send `wc_sessionDelete` for every settled session
+    pub delete_sessions: bool,
+    /// Send `wc_pairingDelete` for the current pairing
+    pub delete_pairing: bool,
+    /// Bound on each step: peer deletes, handling queued requests, stopping
+    /// the actors and flushing responses
+    pub timeout: Duration,
+}
+
+impl Default for ShutdownOptions {
+    fn default() -> Self {
+        Self {
+            delete_sessions: false,
+            delete_pairing: false,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl PairingManager {
+    pub fn is_shut_down(&self) -> bool {
+        self.actors.tasks().is_cancelled()
+    }
+
+    /// Shut down and keep sessions and pairing, so that they can be restored
+    /// by the next manager on the same storage.
+    pub async fn shutdown(&self) -> Result<()> {
+        self.shutdown_with(ShutdownOptions::default()).await
+    }
+
+    /// Stop every actor and background task of this manager, then close the
+    /// socket.
+    ///
+    /// Requests that were already received, including those still queued,
+    /// are handled and their responses go out. Requests that arrive during
+    /// shutdown are dropped. Resolves once everything has terminated, or
+    /// when a step exceeds [`ShutdownOptions::timeout`].
+    pub async fn shutdown_with(&self, opts: ShutdownOptions) -> Result<()> {
+        info!("shutting down");
+        let timeout = u32::try_from(opts.timeout.as_millis()).unwrap_or(u32::MAX);
+        if opts.delete_sessions {
+            self.delete_sessions(timeout).await;
+        }
+        if opts.delete_pairing && self.topic().is_some() {
+            if let Err(e) = self.delete().await {
+                warn!("failed to delete pairing: {e}");
+            }
+        }
+        let tasks = self.actors.tasks();
+        // ends the socket handler, reconnects, auth refresh and heartbeats,
+        // the request loop hands what is still queued to the actors
+        tasks.cancel();
+        if wait::wait_until(timeout, tasks.wait()).await.is_err() {
+            warn!("queued requests were not handled within {timeout}ms");
+        }
+        // the actors finish their mailboxes before they stop
+        if wait::wait_until(timeout, self.actors.stop()).await.is_err() {
+            warn!("actors did not stop within {timeout}ms");
+        }
+        if wait::wait_until(timeout, tasks.wait()).await.is_err() {
+            warn!("{} tasks still running after shutdown", tasks.running());
+        }
+        self.disconnect_socket().await
+    }
+
+    async fn delete_sessions(&self, timeout: u32) {
+        let settlements = self.ciphers.settlements().unwrap_or_default();
+        for settled in settlements {
+            let topic = settled.topic;
+            let result = wait::wait_until(
+                timeout,
+                self.transport.publish_request::<bool>(
+                    topic.clone(),
+                    RequestParams::SessionDelete(SessionDeleteRequest::default()),
+                ),
+            )
+            .await;
+            if !matches!(result, Ok(Ok(true))) {
+                warn!("peer did not confirm delete of session {topic}");
+            }
+            let _ = self.actors.session().send(ClearSession(topic)).await;
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use {
+    crate::{
+        rpc::{Metadata, SdkErrors, SessionRequestRequest},
+        Error,
+        Result,
+        SessionHandler,
+        WalletRequestResponse,
+    },
+    monedero_domain::namespaces::{ChainId, Method},
+    regex::Regex,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    std::{collections::BTreeMap, fmt::Debug, sync::Arc},
+    tokio::sync::Mutex,
+    tracing::{debug, warn},
+};
+
+/// What to do with a matching `wc_sessionRequest`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyOutcome {
+    /// Answer with the approver registered under this name, see
+    /// [`PolicyEngine::approver`]
+    Approve(String),
+    /// Answer with [`SdkErrors::UserRejected`]
+    Reject,
+    /// Hand the request to the session's own [`SessionHandler`]
+    #[default]
+    AskUser,
+}
+
+/// Check on the value at a JSON pointer into the request params, for example
+/// `{ pointer = "/0/to", equals = "0x..." }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamPredicate {
+    pub pointer: String,
+    #[serde(flatten)]
+    pub test: ParamTest,
+}
+
+/// A regex compiled when the policy is loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        Regex::new(&value).map(Self)
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(value: Pattern) -> Self {
+        Self::from(value.0.as_str())
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamTest {
+    Equals(Value),
+    OneOf(Vec<Value>),
+    /// Regex on a string value
+    Matches(Pattern),
+    /// `true` if the value must be present, `false` if it must not
+    Exists(bool),
+}
+
+impl ParamPredicate {
+    fn holds(&self, params: &Value) -> bool {
+        let value = params.pointer(&self.pointer);
+        match &self.test {
+            ParamTest::Equals(expected) => value == Some(expected),
+            ParamTest::OneOf(expected) => value.is_some_and(|v| expected.contains(v)),
+            ParamTest::Matches(pattern) => value
+                .and_then(Value::as_str)
+                .is_some_and(|v| pattern.is_match(v)),
+            ParamTest::Exists(exists) => value.is_some() == *exists,
+        }
+    }
+}
+
+/// A rule matches when every non-empty condition does. Empty lists match
+/// anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicyRule {
+    /// Shown in logs
+    pub name: String,
+    /// Peer `Metadata.url`, compared without trailing slashes. The URL is
+    /// claimed by the dapp and not verified, any dapp can send any URL.
+    pub urls: Vec<String>,
+    pub chains: Vec<ChainId>,
+    pub methods: Vec<Method>,
+    pub params: Vec<ParamPredicate>,
+    pub outcome: PolicyOutcome,
+}
+
+impl PolicyRule {
+    fn matches(&self, peer: &Metadata, request: &SessionRequestRequest) -> bool {
+        let url = peer.url.trim_end_matches('/');
+        (self.urls.is_empty() || self.urls.iter().any(|u| u.trim_end_matches('/') == url))
+            && (self.chains.is_empty() || self.chains.contains(&request.chain_id))
+            && (self.methods.is_empty() || self.methods.contains(&request.request.method))
+            && self.params.iter().all(|p| p.holds(&request.request.params))
+    }
+}
+
+/// Declarative rules for answering `wc_sessionRequest` on a wallet.
+///
+/// Rules are tried in order and the first match decides, `fallback` applies
+/// when none does.
+///
+/// The dapp URL that rules match on is unverified: it is whatever the dapp
+/// claims in its metadata. Don't approve on the URL alone.
+///
+/// ```toml
+/// [[rules]]
+/// name = "devnet signatures"
+/// urls = ["https://dapp.example"]
+/// chains = ["eip155:11155111"]
+/// methods = ["personal_sign"]
+/// outcome = { approve = "signer" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    pub rules: Vec<PolicyRule>,
+    pub fallback: PolicyOutcome,
+}
+
+impl Policy {
+    #[allow(clippy::result_large_err)]
+    pub fn from_json(s: &str) -> Result<Self> {
+        serde_json::from_str(s).map_err(|e| Error::InvalidPolicy(e.to_string()))
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn from_toml(s: &str) -> Result<Self> {
+        toml::from_str(s).map_err(|e| Error::InvalidPolicy(e.to_string()))
+    }
+
+    pub fn evaluate(&self, peer: &Metadata, request: &SessionRequestRequest) -> &PolicyOutcome {
+        let rule = self.rules.iter().find(|r| r.matches(peer, request));
+        if let Some(rule) = rule {
+            debug!("policy rule '{}' matched {request}", rule.name);
+        }
+        rule.map_or(&self.fallback, |r| &r.outcome)
+    }
+}
+
+/// A [`Policy`] with the handlers that its approve outcomes name.
+///
+/// Install on a wallet with [`crate::Wallet::set_policy`].
+#[derive(Clone, Default)]
+pub struct PolicyEngine {
+    policy: Policy,
+    approvers: BTreeMap<String, Arc<dyn SessionHandler>>,
+}
+
+impl Debug for PolicyEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PolicyEngine")
+            .field("policy", &self.policy)
+            .field("approvers", &self.approvers.keys())
+            .finish()
+    }
+}
+
+impl PolicyEngine {
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy,
+            approvers: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn approver(mut self, name: impl Into<String>, handler: impl SessionHandler) -> Self {
+        self.approvers.insert(name.into(), Arc::new(handler));
+        self
+    }
+
+    pub const fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Every approve outcome must name a registered approver.
+    #[allow(clippy::result_large_err)]
+    pub fn check(&self) -> Result<()> {
+        let outcomes = self
+            .policy
+            .rules
+            .iter()
+            .map(|r| &r.outcome)
+            .chain([&self.policy.fallback]);
+        for outcome in outcomes {
+            if let PolicyOutcome::Approve(name) = outcome {
+                if !self.approvers.contains_key(name) {
+                    return Err(Error::InvalidPolicy(format!("no approver named '{name}'")));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The engine of a wallet session, with the metadata of its dapp.
+#[derive(Clone, Debug)]
+pub struct SessionPolicy {
+    pub engine: Arc<PolicyEngine>,
+    pub peer: Metadata,
+}
+
+impl SessionPolicy {
+    pub async fn request(
+        &self,
+        request: SessionRequestRequest,
+        ask: &Mutex<Box<dyn SessionHandler>>,
+    ) -> WalletRequestResponse {
+        match self.engine.policy.evaluate(&self.peer, &request) {
+            PolicyOutcome::Approve(name) => {
+                if let Some(approver) = self.engine.approvers.get(name) {
+                    return approver.request(request).await;
+                }
+                warn!("no approver named '{name}', asking user");
+            }
+            PolicyOutcome::Reject => {
+                return WalletRequestResponse::Error(SdkErrors::UserRejected);
+            }
+            PolicyOutcome::AskUser => {}
+        }
+        ask.lock().await.request(request).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::rpc::RequestMethod,
+        monedero_domain::namespaces::{AlloyChain, EipMethod},
+        serde_json::json,
+    };
+
+    fn request(method: &str, params: Value) -> SessionRequestRequest {
+        SessionRequestRequest {
+            request: RequestMethod {
+                method: method.parse().unwrap(),
+                params,
+                expiry: None,
+            },
+            chain_id: ChainId::EIP155(AlloyChain::sepolia()),
+        }
+    }
+
+    #[test]
+    fn policy_toml_and_json() -> anyhow::Result<()> {
+        let from_toml = Policy::from_toml(
+            r#"
+            fallback = "reject"
+
+            [[rules]]
+            name = "devnet"
+            urls = ["https://dapp.example/"]
+            chains = ["eip155:11155111"]
+            methods = ["personal_sign"]
+            params = [{ pointer = "/1", matches = "(?i)^0xab" }]
+            outcome = { approve = "signer" }
+
+            [[rules]]
+            methods = ["eth_sendTransaction"]
+            params = [{ pointer = "/0/value", exists = false }]
+            outcome = "ask-user"
+            "#,
+        )?;
+        let from_json = Policy::from_json(
+            r#"{
+                "fallback": "reject",
+                "rules": [
+                    {
+                        "name": "devnet",
+                        "urls": ["https://dapp.example/"],
+                        "chains": ["eip155:11155111"],
+                        "methods": ["personal_sign"],
+                        "params": [{ "pointer": "/1", "matches": "(?i)^0xab" }],
+                        "outcome": { "approve": "signer" }
+                    },
+                    {
+                        "methods": ["eth_sendTransaction"],
+                        "params": [{ "pointer": "/0/value", "exists": false }],
+                        "outcome": "ask-user"
+                    }
+                ]
+            }"#,
+        )?;
+        assert_eq!(from_toml, from_json);
+        assert_eq!(
+            from_toml,
+            serde_json::from_value(serde_json::to_value(&from_toml)?)?
+        );
+        assert_eq!(
+            vec![Method::EIP155(EipMethod::PersonalSign)],
+            from_toml.rules[0].methods
+        );
+
+        let peer = Metadata {
+            url: String::from("https://dapp.example"),
+            ..Default::default()
+        };
+        let other = Metadata::default();
+        let sign = request("personal_sign", json!(["hi", "0xAB12"]));
+        assert_eq!(
+            &PolicyOutcome::Approve(String::from("signer")),
+            from_toml.evaluate(&peer, &sign)
+        );
+        assert_eq!(&PolicyOutcome::Reject, from_toml.evaluate(&other, &sign));
+        let sign = request("personal_sign", json!(["hi", "0xcd"]));
+        assert_eq!(&PolicyOutcome::Reject, from_toml.evaluate(&peer, &sign));
+
+        let send = request("eth_sendTransaction", json!([{"to": "0x1"}]));
+        assert_eq!(&PolicyOutcome::AskUser, from_toml.evaluate(&other, &send));
+        let send = request(
+            "eth_sendTransaction",
+            json!([{"to": "0x1", "value": "0x1"}]),
+        );
+        assert_eq!(&PolicyOutcome::Reject, from_toml.evaluate(&other, &send));
+
+        assert!(
+            Policy::from_json(r#"{"rules":[{"params":[{"pointer":"/0","matches":"("}]}]}"#)
+                .is_err()
+        );
+        assert!(PolicyEngine::new(from_toml).check().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Capacities of the actor mailboxes and of the channels that carry relay
+/// messages to the actors.
+///
+/// The relay reader cannot wait for a full queue, so inbound requests that
+/// do not fit are shed: the peer gets a
+/// [`crate::rpc::OverloadErrors::Busy`] error response right away, code
+/// `11000`, which is not part of the Sign API spec. When
+/// [`QueueLimits::busy_replies`] of those are still waiting for the
+/// transport, further requests are dropped without an answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueLimits {
+    /// `None` keeps actor mailboxes unbounded
+    pub mailbox: Option<usize>,
+    /// Peer requests waiting to be handled
+    pub inbound_requests: usize,
+    /// Responses to our requests waiting to be matched. Overflow is dropped
+    /// and the request times out.
+    pub inbound_responses: usize,
+    /// Busy replies waiting to be published
+    pub busy_replies: usize,
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self {
+            mailbox: None,
+            inbound_requests: 1024,
+            inbound_responses: 1024,
+            busy_replies: 64,
+        }
+    }
+}
+
+/// Snapshot of how many messages are waiting, see
+/// [`crate::PairingManager::queue_depth`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueDepth {
+    pub inbound_requests: usize,
+    pub inbound_responses: usize,
+    pub request_actor: usize,
+    pub proposal_actor: usize,
+    pub session_actor: usize,
+    pub transport_actor: usize,
+    pub response_actor: usize,
+    /// Requests answered with busy or dropped because the queue was full,
+    /// since the manager started
+    pub shed: u64,
+}
+
+/// Counters shared by the relay handler and its event loops.
+#[derive(Default)]
+pub struct InboundStats {
+    requests: AtomicUsize,
+    responses: AtomicUsize,
+    shed: AtomicU64,
+}
+
+pub type SharedInboundStats = Arc<InboundStats>;
+
+impl InboundStats {
+    pub fn request_queued(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn request_taken(&self) {
+        self.requests.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn response_queued(&self) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn response_taken(&self) {
+        self.responses.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn request_shed(&self) {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    pub fn responses(&self) -> usize {
+        self.responses.load(Ordering::Relaxed)
+    }
+
+    pub fn shed(&self) -> u64 {
+        self.shed.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,189 @@
+use {
+    dashmap::DashMap,
+    monedero_domain::Topic,
+    std::{collections::BTreeMap, sync::Arc, time::Duration},
+};
+
+/// Token bucket: `burst` requests at once, then one more every `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub interval: Duration,
+}
+
+impl RateLimit {
+    pub const fn new(burst: u32, interval: Duration) -> Self {
+        Self { burst, interval }
+    }
+}
+
+/// Limits on inbound peer requests, counted per topic and method.
+///
+/// Requests over the limit are answered with
+/// [`crate::rpc::OverloadErrors::RateLimited`], code `11001`, which is not
+/// part of the Sign API spec, and reported as
+/// [`crate::SessionLifecycleEvent::RateLimited`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Methods that prompt the user, such as `wc_sessionRequest`
+    pub prompt: Option<RateLimit>,
+    /// Every other method
+    pub other: Option<RateLimit>,
+    /// Per method overrides, keyed by JSON-RPC method name, `None` disables
+    /// the limit for that method
+    pub methods: BTreeMap<String, Option<RateLimit>>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            prompt: Some(RateLimit::new(10, Duration::from_secs(1))),
+            other: Some(RateLimit::new(60, Duration::from_millis(50))),
+            methods: BTreeMap::new(),
+        }
+    }
+}
+
+impl RateLimits {
+    /// No limits at all.
+    pub fn disabled() -> Self {
+        Self {
+            prompt: None,
+            other: None,
+            methods: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_method(mut self, method: impl Into<String>, limit: Option<RateLimit>) -> Self {
+        self.methods.insert(method.into(), limit);
+        self
+    }
+
+    fn limit(&self, method: &str, prompt: bool) -> Option<RateLimit> {
+        if let Some(limit) = self.methods.get(method) {
+            return *limit;
+        }
+        if prompt {
+            self.prompt
+        } else {
+            self.other
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: u32,
+    /// Epoch millis up to which tokens were added
+    refilled: i64,
+}
+
+impl Bucket {
+    const fn full(limit: RateLimit, now: i64) -> Self {
+        Self {
+            tokens: limit.burst,
+            refilled: now,
+        }
+    }
+
+    fn take(&mut self, limit: RateLimit, now: i64) -> bool {
+        let interval = i64::try_from(limit.interval.as_millis())
+            .unwrap_or(i64::MAX)
+            .max(1);
+        let earned = now.saturating_sub(self.refilled) / interval;
+        if earned > 0 {
+            let earned = u32::try_from(earned).unwrap_or(u32::MAX);
+            self.tokens = self.tokens.saturating_add(earned).min(limit.burst);
+            self.refilled = if self.tokens == limit.burst {
+                now
+            } else {
+                self.refilled
+                    .saturating_add(i64::from(earned).saturating_mul(interval))
+            };
+        }
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        true
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    limits: Arc<RateLimits>,
+    buckets: Arc<DashMap<(Topic, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits: Arc::new(limits),
+            buckets: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// True if a request for `method` on `topic` is within the limit.
+    pub fn allow(&self, topic: &Topic, method: &str, prompt: bool) -> bool {
+        self.allow_at(topic, method, prompt, chrono::Utc::now().timestamp_millis())
+    }
+
+    fn allow_at(&self, topic: &Topic, method: &str, prompt: bool, now: i64) -> bool {
+        let Some(limit) = self.limits.limit(method, prompt) else {
+            return true;
+        };
+        self.buckets
+            .entry((topic.clone(), String::from(method)))
+            .or_insert_with(|| Bucket::full(limit, now))
+            .take(limit, now)
+    }
+
+    /// Forget the buckets of a deleted session or pairing.
+    pub fn forget(&self, topic: &Topic) {
+        self.buckets.retain(|(t, _), _| t != topic);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        let limits = RateLimits {
+            prompt: Some(RateLimit::new(2, Duration::from_secs(1))),
+            other: None,
+            methods: BTreeMap::new(),
+        }
+        .with_method(
+            "wc_sessionPing",
+            Some(RateLimit::new(1, Duration::from_secs(10))),
+        );
+        let limiter = RateLimiter::new(limits);
+        let a = Topic::generate();
+        let b = Topic::generate();
+
+        assert!(limiter.allow_at(&a, "wc_sessionRequest", true, 0));
+        assert!(limiter.allow_at(&a, "wc_sessionRequest", true, 0));
+        assert!(!limiter.allow_at(&a, "wc_sessionRequest", true, 500));
+        // other topics and methods have their own bucket
+        assert!(limiter.allow_at(&b, "wc_sessionRequest", true, 500));
+        assert!(limiter.allow_at(&a, "wc_sessionEvent", false, 500));
+        assert!(limiter.allow_at(&a, "wc_sessionEvent", false, 500));
+
+        assert!(limiter.allow_at(&a, "wc_sessionRequest", true, 1000));
+        assert!(!limiter.allow_at(&a, "wc_sessionRequest", true, 1999));
+        // refills up to burst only
+        assert!(limiter.allow_at(&a, "wc_sessionRequest", true, 60_000));
+        assert!(limiter.allow_at(&a, "wc_sessionRequest", true, 60_000));
+        assert!(!limiter.allow_at(&a, "wc_sessionRequest", true, 60_000));
+
+        assert!(limiter.allow_at(&a, "wc_sessionPing", false, 0));
+        assert!(!limiter.allow_at(&a, "wc_sessionPing", false, 9_999));
+        assert!(limiter.allow_at(&a, "wc_sessionPing", false, 10_000));
+
+        limiter.forget(&a);
+        assert!(limiter.allow_at(&a, "wc_sessionPing", false, 10_001));
+    }
+}
@@ -1,45 +1,152 @@
 use {
     crate::{
-        actors::{InboundResponseActor, RequestHandlerActor},
-        rpc::{Payload, Response, RpcRequest},
-        spawn_task,
+        actors::{ActorConfig, InboundResponseActor, RequestHandlerActor, TransportActor},
+        custom::CustomMethods,
+        middleware::{Flow, MiddlewareChain},
+        queues::SharedInboundStats,
+        rpc::{
+            OverloadErrors,
+            Payload,
+            Request,
+            RequestParams,
+            Response,
+            RpcRequest,
+            RpcResponse,
+            RpcResponsePayload,
+        },
+        tasks::Tasks,
+        DisconnectReason,
         SocketEvent,
     },
     monedero_cipher::Cipher,
     monedero_domain::Message,
     monedero_relay::{ClientError, CloseFrame, ConnectionHandler},
-    tokio::sync::mpsc,
-    tracing::{error, info, trace, warn},
+    std::sync::Arc,
+    tokio::sync::{
+        mpsc::{self, error::TrySendError},
+        Semaphore,
+    },
+    tracing::{debug, error, info, trace, warn},
     xtra::prelude::*,
 };
 
 pub struct RelayHandler {
     cipher: Cipher,
-    req_tx: mpsc::UnboundedSender<RpcRequest>,
-    res_tx: mpsc::UnboundedSender<Response>,
+    req_tx: mpsc::Sender<RpcRequest>,
+    res_tx: mpsc::Sender<Response>,
     socket_tx: mpsc::UnboundedSender<SocketEvent>,
+    transport: Address<TransportActor>,
+    stats: SharedInboundStats,
+    custom: CustomMethods,
+    tasks: Tasks,
+    /// Permits for busy replies waiting on the transport
+    busy: Arc<Semaphore>,
+}
+
+pub struct InboundActors {
+    pub request: Address<RequestHandlerActor>,
+    pub response: Address<InboundResponseActor>,
+    pub transport: Address<TransportActor>,
 }
 
 impl RelayHandler {
     pub(crate) fn new(
         cipher: Cipher,
-        request_actor: Address<RequestHandlerActor>,
-        response_actor: Address<InboundResponseActor>,
+        actors: InboundActors,
         socket_tx: mpsc::UnboundedSender<SocketEvent>,
+        config: &ActorConfig,
+        stats: SharedInboundStats,
+        tasks: &Tasks,
     ) -> Self {
-        let (req_tx, req_rx) = mpsc::unbounded_channel::<RpcRequest>();
-        let (res_tx, res_rx) = mpsc::unbounded_channel::<Response>();
-        spawn_task(async move {
-            event_loop_request(req_rx, request_actor).await;
+        let limits = &config.queues;
+        let (req_tx, req_rx) = mpsc::channel::<RpcRequest>(limits.inbound_requests.max(1));
+        let (res_tx, res_rx) = mpsc::channel::<Response>(limits.inbound_responses.max(1));
+        let request_loop = RequestLoop {
+            actor: actors.request,
+            transport: actors.transport.clone(),
+            middleware: config.middleware.clone(),
+            stats: stats.clone(),
+            tasks: tasks.clone(),
+        };
+        // left to finish on shutdown, it hands the queued requests to the
+        // actors first
+        tasks.spawn_flushed(async move {
+            event_loop_request(req_rx, request_loop).await;
         });
-        spawn_task(async move {
-            event_loop_response(res_rx, response_actor).await;
+        let response_stats = stats.clone();
+        let middleware = config.middleware.clone();
+        tasks.spawn(async move {
+            event_loop_response(res_rx, actors.response, middleware, response_stats).await;
         });
         Self {
             cipher,
             req_tx,
             res_tx,
             socket_tx,
+            transport: actors.transport,
+            stats,
+            custom: config.custom.clone(),
+            tasks: tasks.clone(),
+            busy: Arc::new(Semaphore::new(limits.busy_replies)),
+        }
+    }
+}
+
+impl RelayHandler {
+    /// Answer a request that does not fit in the queue with busy, or drop
+    /// it when too many busy replies are already pending.
+    fn shed(&self, rpc: RpcRequest) {
+        self.stats.request_shed();
+        let Ok(permit) = self.busy.clone().try_acquire_owned() else {
+            warn!("too many busy replies pending, dropping {}", rpc.payload.id);
+            return;
+        };
+        warn!("request queue is full, shedding {}", rpc.payload.id);
+        let response = RpcResponse {
+            id: rpc.payload.id,
+            payload: RpcResponsePayload::Error(
+                rpc.payload.params.error_response(OverloadErrors::Busy.into()),
+            ),
+            topic: rpc.topic,
+        };
+        let transport = self.transport.clone();
+        self.tasks.spawn(async move {
+            let _permit = permit;
+            if let Err(e) = transport.send(response).await {
+                warn!("failed to send busy response: {e}");
+            }
+        });
+    }
+
+    /// Fill in the metadata of a custom request. Unknown methods that came
+    /// with the tag of a registered one get answered by the session, others
+    /// are dropped.
+    fn resolve_custom(&self, tag: u32, request: &mut Request) -> bool {
+        let RequestParams::Custom(custom) = &mut request.params else {
+            return true;
+        };
+        let metadata = self
+            .custom
+            .get(&custom.method)
+            .or_else(|| self.custom.by_request_tag(tag));
+        if let Some(metadata) = metadata {
+            custom.metadata = metadata;
+            return true;
+        }
+        warn!(
+            "dropping request {} for unknown method {}",
+            request.id, custom.method
+        );
+        false
+    }
+
+    fn force_disconnect(&self, reason: DisconnectReason) {
+        if self
+            .socket_tx
+            .send(SocketEvent::ForceDisconnect(reason))
+            .is_err()
+        {
+            warn!("failed to send socket event");
         }
     }
 }
@@ -51,14 +158,13 @@ impl ConnectionHandler for RelayHandler {
         }
     }
 
-    fn disconnected(&mut self, _frame: Option<CloseFrame<'static>>) {
-        if self.socket_tx.send(SocketEvent::ForceDisconnect).is_err() {
-            warn!("failed to send socket event");
-        }
+    fn disconnected(&mut self, frame: Option<CloseFrame<'static>>) {
+        let reason = frame.map(DisconnectReason::from).unwrap_or_default();
+        self.force_disconnect(reason);
     }
 
     fn message_received(&mut self, message: Message) {
-        if !Payload::irn_tag_in_range(message.tag) {
+        if !Payload::irn_tag_in_range(message.tag) && !self.custom.has_tag(message.tag) {
             warn!("\ntag={} skip handling", message.tag);
             return;
         }
@@ -67,18 +173,41 @@ impl ConnectionHandler for RelayHandler {
             .cipher
             .decode::<Payload>(&message.topic, &message.message)
         {
-            Ok(Payload::Request(req)) => {
+            Ok(Payload::Request(mut req)) => {
+                if !self.resolve_custom(message.tag, &mut req) {
+                    return;
+                }
                 let rpc: RpcRequest = RpcRequest {
                     topic: message.topic,
                     payload: req,
                 };
-                if let Err(e) = self.req_tx.send(rpc) {
-                    warn!("[relay handler] request channel is broken, error: {e}");
+                // counted before the send so the event loop never takes
+                // a request that was not counted yet
+                self.stats.request_queued();
+                match self.req_tx.try_send(rpc) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(rpc)) => {
+                        self.stats.request_taken();
+                        self.shed(rpc);
+                    }
+                    Err(TrySendError::Closed(rpc)) => {
+                        self.stats.request_taken();
+                        warn!("request queue is closed, dropping {}", rpc.payload.id);
+                    }
                 }
             }
             Ok(Payload::Response(res)) => {
-                if let Err(e) = self.res_tx.send(res) {
-                    warn!("[relay handler] response channel is broken, error: {e}");
+                self.stats.response_queued();
+                match self.res_tx.try_send(res) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(res)) => {
+                        self.stats.response_taken();
+                        warn!("response queue is full, dropping response {}", res.id);
+                    }
+                    Err(TrySendError::Closed(_)) => {
+                        self.stats.response_taken();
+                        warn!("[relay handler] response channel is broken");
+                    }
                 }
             }
             Err(err) => {
@@ -87,22 +216,74 @@ impl ConnectionHandler for RelayHandler {
         }
     }
 
-    fn inbound_error(&mut self, _error: ClientError) {
-        self.disconnected(None);
+    fn inbound_error(&mut self, error: ClientError) {
+        self.force_disconnect(error.into());
     }
 
-    fn outbound_error(&mut self, _error: ClientError) {
-        self.disconnected(None);
+    fn outbound_error(&mut self, error: ClientError) {
+        self.force_disconnect(error.into());
     }
 }
 
-async fn event_loop_request(
-    mut rx: mpsc::UnboundedReceiver<RpcRequest>,
+struct RequestLoop {
     actor: Address<RequestHandlerActor>,
-) {
+    transport: Address<TransportActor>,
+    middleware: MiddlewareChain,
+    stats: SharedInboundStats,
+    tasks: Tasks,
+}
+
+impl RequestLoop {
+    /// Run the middleware chain, answering the peer if it stops the request.
+    async fn inbound_middleware(&self, req: RpcRequest) -> Option<RpcRequest> {
+        if self.middleware.is_empty() {
+            return Some(req);
+        }
+        // a stop needs the request params to pick the error response type
+        let (id, topic, params) = (
+            req.payload.id,
+            req.topic.clone(),
+            req.payload.params.clone(),
+        );
+        let error = match self.middleware.inbound_request(req).await {
+            Flow::Next(req) => return Some(req),
+            Flow::Stop(error) => error,
+        };
+        let response = RpcResponse {
+            id,
+            topic,
+            payload: RpcResponsePayload::Error(params.error_response(error)),
+        };
+        if let Err(e) = self.transport.send(response).await {
+            warn!("failed to send middleware response: {e}");
+        }
+        None
+    }
+}
+
+/// Hands inbound requests to the request actor. On shutdown the queue is
+/// closed and what is left in it is still handed over.
+async fn event_loop_request(mut rx: mpsc::Receiver<RpcRequest>, lp: RequestLoop) {
     info!("started event loop for requests");
-    while let Some(req) = rx.recv().await {
-        if let Err(err) = actor.send(req).await {
+    let mut draining = false;
+    loop {
+        let req = tokio::select! {
+            req = rx.recv() => req,
+            () = lp.tasks.cancelled(), if !draining => {
+                debug!("draining {} queued requests", rx.len());
+                rx.close();
+                draining = true;
+                continue;
+            }
+        };
+        let Some(req) = req else {
+            return;
+        };
+        lp.stats.request_taken();
+        let Some(req) = lp.inbound_middleware(req).await else {
+            continue;
+        };
+        if let Err(err) = lp.actor.send(req).await {
             error!("request actor shutdown! {err}");
             return;
         }
@@ -110,14 +291,77 @@ async fn event_loop_request(
 }
 
 async fn event_loop_response(
-    mut rx: mpsc::UnboundedReceiver<Response>,
+    mut rx: mpsc::Receiver<Response>,
     actor: Address<InboundResponseActor>,
+    middleware: MiddlewareChain,
+    stats: SharedInboundStats,
 ) {
     info!("started event loop for response");
     while let Some(r) = rx.recv().await {
+        stats.response_taken();
+        let r = middleware.inbound_response(r).await;
         if let Err(e) = actor.send(r).await {
             warn!("actor channel has closed: {e}");
             return;
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{queues::QueueLimits, rpc::PairPingRequest, KvStorage},
+        monedero_domain::{MessageId, Topic},
+    };
+
+    #[tokio::test]
+    async fn test_busy_replies_are_bounded() -> anyhow::Result<()> {
+        let cipher = Cipher::new(Arc::new(KvStorage::mem()), None)?;
+        // the actors never run, so the transport mailbox stays full
+        let (request, _request_mailbox) = Mailbox::bounded(1);
+        let (response, _response_mailbox) = Mailbox::bounded(1);
+        let (transport, _transport_mailbox) = Mailbox::bounded(1);
+        let config = ActorConfig {
+            queues: QueueLimits {
+                inbound_requests: 1,
+                busy_replies: 4,
+                ..QueueLimits::default()
+            },
+            ..ActorConfig::default()
+        };
+        let (socket_tx, _socket_rx) = mpsc::unbounded_channel();
+        let stats = SharedInboundStats::default();
+        let tasks = Tasks::default();
+        let handler = RelayHandler::new(
+            cipher,
+            InboundActors {
+                request,
+                response,
+                transport,
+            },
+            socket_tx,
+            &config,
+            stats.clone(),
+            &tasks,
+        );
+        // the request and response loops
+        let loops = tasks.running();
+        let topic = Topic::generate();
+        for id in 0..100 {
+            handler.shed(RpcRequest {
+                topic: topic.clone(),
+                payload: Request::new(
+                    MessageId::new(id),
+                    RequestParams::PairPing(PairPingRequest {}),
+                ),
+            });
+        }
+        tokio::task::yield_now().await;
+        assert_eq!(loops + 4, tasks.running());
+        assert_eq!(100, stats.shed());
+        tasks.cancel();
+        tasks.wait().await;
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 //! The crate exports common types used when interacting with messages between
 //! clients. This also includes communication over HTTP between relays.
 
+mod overload;
 mod params;
+mod peer_error;
 mod sdkerrors;
 
 use {
@@ -12,7 +14,12 @@ use {
         sync::Arc,
     },
 };
-pub use {params::*, sdkerrors::SdkErrors};
+pub use {
+    overload::OverloadErrors,
+    params::*,
+    peer_error::{ErrorCode, PeerError},
+    sdkerrors::SdkErrors,
+};
 
 /// Version of the WalletConnect protocol that we're implementing.
 pub const JSON_RPC_VERSION_STR: &str = "2.0";
@@ -111,10 +118,11 @@ impl RpcResponse {
     }
 }
 
+/// A request from the peer and the topic it came in on.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub(crate) struct RpcRequest {
-    pub(crate) topic: Topic,
-    pub(crate) payload: Request,
+pub struct RpcRequest {
+    pub topic: Topic,
+    pub payload: Request,
 }
 
 impl Display for RpcRequest {
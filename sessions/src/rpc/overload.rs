@@ -0,0 +1,63 @@
+use crate::rpc::ErrorParams;
+
+/// Errors this crate answers with when it does not take a peer request.
+///
+/// These are not part of the Sign API spec, so they are kept apart from
+/// [`crate::rpc::SdkErrors`]. Peers on other SDKs see an unknown code with
+/// the message below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(strum_macros::EnumIter))]
+pub enum OverloadErrors {
+    /// Code `11000`, the inbound request queue is full
+    Busy,
+    /// Code `11001`, the peer is over its [`crate::RateLimits`]
+    RateLimited,
+}
+
+impl OverloadErrors {
+    pub const fn code(self) -> i64 {
+        match self {
+            Self::Busy => 11000,
+            Self::RateLimited => 11001,
+        }
+    }
+
+    pub const fn message(self) -> &'static str {
+        match self {
+            Self::Busy => "Busy, try again later.",
+            Self::RateLimited => "Too many requests.",
+        }
+    }
+
+    /// The variant that is sent with `code`.
+    pub const fn from_code(code: i64) -> Option<Self> {
+        match code {
+            11000 => Some(Self::Busy),
+            11001 => Some(Self::RateLimited),
+            _ => None,
+        }
+    }
+}
+
+#[allow(clippy::cast_sign_loss)]
+impl From<OverloadErrors> for ErrorParams {
+    fn from(value: OverloadErrors) -> Self {
+        Self {
+            code: Some(value.code() as u64),
+            message: String::from(value.message()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::rpc::SdkErrors, strum::IntoEnumIterator};
+
+    #[test]
+    fn code_round_trip() {
+        for e in OverloadErrors::iter() {
+            assert_eq!(Some(e), OverloadErrors::from_code(e.code()), "{e:?}");
+            assert_eq!(None, SdkErrors::from_code(e.code()), "{e:?}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub(super) mod custom;
 pub(super) mod pair_delete;
 pub(super) mod pair_extend;
 pub(super) mod pair_ping;
@@ -22,6 +23,7 @@ use {
     },
 };
 pub use {
+    custom::*,
     pair_delete::*,
     pair_extend::*,
     pair_ping::*,
@@ -77,7 +79,7 @@ pub trait RelayProtocolHelpers {
 ///
 /// https://specs.walletconnect.com/2.0/specs/servers/relay/relay-server-rpc
 /// #definitions
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct IrnMetadata {
     pub tag: u32,
     pub ttl: u64,
@@ -103,6 +105,7 @@ macro_rules! impl_relay_protocol_metadata {
                         [<$param_type>]::PairPing(_) => pair_ping::[<IRN_ $meta:upper _METADATA>],
                         [<$param_type>]::PairDelete(_) => pair_delete::[<IRN_ $meta:upper _METADATA>],
                         [<$param_type>]::PairExtend(_) => pair_extend::[<IRN_ $meta:upper _METADATA>],
+                        [<$param_type>]::Custom(c) => c.irn_metadata(),
                     }
                 }
             }
@@ -179,6 +182,9 @@ pub enum RequestParams {
     SessionDelete(SessionDeleteRequest),
     #[serde(rename = "wc_sessionPing")]
     SessionPing(()),
+    /// Any other method, see [`crate::CustomMethod`]
+    #[serde(untagged)]
+    Custom(CustomRequest),
 }
 
 impl Display for RequestParams {
@@ -195,11 +201,52 @@ impl Display for RequestParams {
             Self::SessionEvent(args) => &format!("sessionEvent: {}", args.event.name),
             Self::SessionDelete(_) => "sessionDelete",
             Self::SessionPing(()) => "sessionPing",
+            Self::Custom(args) => &format!("custom: {}", args.method),
         };
         write!(f, "{req}")
     }
 }
 
+impl RequestParams {
+    /// JSON-RPC method name, as sent on the wire.
+    pub fn method(&self) -> &str {
+        match self {
+            Self::PairDelete(_) => "wc_pairingDelete",
+            Self::PairExtend(_) => "wc_pairingExtend",
+            Self::PairPing(_) => "wc_pairingPing",
+            Self::SessionPropose(_) => "wc_sessionPropose",
+            Self::SessionSettle(_) => "wc_sessionSettle",
+            Self::SessionUpdate(_) => "wc_sessionUpdate",
+            Self::SessionExtend(_) => "wc_sessionExtend",
+            Self::SessionRequest(_) => "wc_sessionRequest",
+            Self::SessionEvent(_) => "wc_sessionEvent",
+            Self::SessionDelete(_) => "wc_sessionDelete",
+            Self::SessionPing(()) => "wc_sessionPing",
+            Self::Custom(args) => &args.method,
+        }
+    }
+
+    /// Error response of the matching method.
+    pub fn error_response(&self, error: ErrorParams) -> ResponseParamsError {
+        match self {
+            Self::PairDelete(_) => ResponseParamsError::PairDelete(error),
+            Self::PairExtend(_) => ResponseParamsError::PairExtend(error),
+            Self::PairPing(_) => ResponseParamsError::PairPing(error),
+            Self::SessionPropose(_) => ResponseParamsError::SessionPropose(error),
+            Self::SessionSettle(_) => ResponseParamsError::SessionSettle(error),
+            Self::SessionUpdate(_) => ResponseParamsError::SessionUpdate(error),
+            Self::SessionExtend(_) => ResponseParamsError::SessionExtend(error),
+            Self::SessionRequest(_) => ResponseParamsError::SessionRequest(error),
+            Self::SessionEvent(_) => ResponseParamsError::SessionEvent(error),
+            Self::SessionDelete(_) => ResponseParamsError::SessionDelete(error),
+            Self::SessionPing(()) => ResponseParamsError::SessionPing(error),
+            Self::Custom(args) => {
+                ResponseParamsError::Custom(CustomResponse::new(error, args.metadata.response))
+            }
+        }
+    }
+}
+
 impl_relay_protocol_metadata!(RequestParams, request);
 
 /// https://www.jsonrpc.org/specification#response_object
@@ -237,6 +284,7 @@ pub enum ResponseParamsSuccess {
     PairPing(bool),
     PairDelete(bool),
     PairExtend(bool),
+    Custom(CustomResponse<Value>),
 }
 impl_relay_protocol_metadata!(ResponseParamsSuccess, response);
 impl_relay_protocol_helpers!(ResponseParamsSuccess);
@@ -288,6 +336,7 @@ pub enum ResponseParamsError {
     PairPing(ErrorParams),
     PairDelete(ErrorParams),
     PairExtend(ErrorParams),
+    Custom(CustomResponse<ErrorParams>),
 }
 
 impl_relay_protocol_metadata!(ResponseParamsError, response);
@@ -0,0 +1,115 @@
+//! App specific methods, see [`crate::CustomMethod`].
+
+use {
+    super::IrnMetadata,
+    crate::rpc::{ErrorParams, IntoUnknownError, RelayProtocolMetadata, ResponseParamsError},
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+};
+
+/// IRN metadata of a custom method, in both directions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CustomMetadata {
+    pub request: IrnMetadata,
+    pub response: IrnMetadata,
+}
+
+/// Request of a method that is not part of the Sign API.
+///
+/// The metadata is not sent, it is looked up by method name when the
+/// request arrives. `wc_` methods never deserialize into this, so malformed
+/// Sign API requests are still errors.
+#[derive(Debug, Serialize, PartialEq, Eq, Deserialize, Clone)]
+#[serde(try_from = "RawCustomRequest")]
+pub struct CustomRequest {
+    pub method: String,
+    pub params: Value,
+    #[serde(skip)]
+    pub metadata: CustomMetadata,
+}
+
+#[derive(Deserialize)]
+struct RawCustomRequest {
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+impl TryFrom<RawCustomRequest> for CustomRequest {
+    type Error = String;
+
+    fn try_from(raw: RawCustomRequest) -> Result<Self, Self::Error> {
+        if raw.method.starts_with("wc_") {
+            return Err(format!("invalid params for {}", raw.method));
+        }
+        Ok(Self {
+            method: raw.method,
+            params: raw.params,
+            metadata: CustomMetadata::default(),
+        })
+    }
+}
+
+impl RelayProtocolMetadata for CustomRequest {
+    fn irn_metadata(&self) -> IrnMetadata {
+        self.metadata.request
+    }
+}
+
+impl IntoUnknownError for CustomRequest {
+    fn unknown(&self) -> ResponseParamsError {
+        ResponseParamsError::Custom(CustomResponse::new(
+            ErrorParams::unknown(),
+            self.metadata.response,
+        ))
+    }
+}
+
+/// Result or error of a [`CustomRequest`], serialized as `value` alone.
+#[derive(Debug, Serialize, PartialEq, Eq, Deserialize, Clone)]
+#[serde(transparent)]
+pub struct CustomResponse<T> {
+    pub value: T,
+    #[serde(skip)]
+    pub irn: IrnMetadata,
+}
+
+impl<T> CustomResponse<T> {
+    pub const fn new(value: T, irn: IrnMetadata) -> Self {
+        Self { value, irn }
+    }
+}
+
+impl<T> RelayProtocolMetadata for CustomResponse<T> {
+    fn irn_metadata(&self) -> IrnMetadata {
+        self.irn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{super::tests::param_json_trim, *},
+        crate::rpc::{Request, RequestParams},
+        anyhow::Result,
+    };
+
+    #[test]
+    fn test_serde_custom_request() -> Result<()> {
+        let json = r#"{"id":1,"jsonrpc":"2.0","method":"app_sync","params":{"cursor":7}}"#;
+        let request: Request = serde_json::from_str(json)?;
+        assert_matches::assert_matches!(
+            &request.params,
+            RequestParams::Custom(CustomRequest { method, .. }) if method == "app_sync"
+        );
+        assert_eq!(param_json_trim(json), serde_json::to_string(&request)?);
+
+        // Sign API methods are still typed
+        let request = Request::new(1.into(), RequestParams::SessionPing(()));
+        let json = serde_json::to_string(&request)?;
+        assert_eq!(request, serde_json::from_str(&json)?);
+        let json = r#"{"id":1,"jsonrpc":"2.0","method":"wc_sessionPing","params":{}}"#;
+        assert!(serde_json::from_str::<Request>(json).is_err());
+        Ok(())
+    }
+}
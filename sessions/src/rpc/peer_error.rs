@@ -0,0 +1,118 @@
+use {
+    super::{OverloadErrors, SdkErrors},
+    serde_json::Value,
+    std::fmt::{Display, Formatter},
+};
+
+/// Code of an error response, see [`PeerError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// One of the codes of the Sign API spec
+    Sdk(SdkErrors),
+    /// One of the codes this crate sends outside of the spec
+    Overload(OverloadErrors),
+    /// Any other code, as sent by the peer
+    Unknown(i64),
+}
+
+impl ErrorCode {
+    pub fn value(&self) -> i64 {
+        match self {
+            Self::Sdk(e) => e.code(),
+            Self::Overload(e) => e.code(),
+            Self::Unknown(code) => *code,
+        }
+    }
+}
+
+impl From<i64> for ErrorCode {
+    fn from(code: i64) -> Self {
+        if let Some(e) = SdkErrors::from_code(code) {
+            return Self::Sdk(e);
+        }
+        OverloadErrors::from_code(code).map_or(Self::Unknown(code), Self::Overload)
+    }
+}
+
+/// Error response of the peer to one of our requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerError {
+    /// Missing on some responses, for example on session expiry
+    pub code: Option<ErrorCode>,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl PeerError {
+    /// The [`SdkErrors`] variant of the code, if it is a known one.
+    pub const fn sdk(&self) -> Option<SdkErrors> {
+        match self.code {
+            Some(ErrorCode::Sdk(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The [`OverloadErrors`] variant of the code, if it is one.
+    pub const fn overload(&self) -> Option<OverloadErrors> {
+        match self.code {
+            Some(ErrorCode::Overload(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PeerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "{} (code {})", self.message, code.value()),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Peers send anything as error, so parsing never fails. What cannot be
+/// read ends up as message.
+impl From<Value> for PeerError {
+    fn from(value: Value) -> Self {
+        let code = value
+            .get("code")
+            .and_then(|c| c.as_i64().or_else(|| c.as_str()?.parse().ok()))
+            .map(ErrorCode::from);
+        let message = match value.get("message") {
+            Some(Value::String(m)) => m.clone(),
+            Some(m) => m.to_string(),
+            None if code.is_none() => value.to_string(),
+            None => String::new(),
+        };
+        Self {
+            code,
+            message,
+            data: value.get("data").filter(|d| !d.is_null()).cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn test_peer_error() {
+        let e = PeerError::from(json!({"code": 5000, "message": "User rejected."}));
+        assert_eq!(Some(SdkErrors::UserRejected), e.sdk());
+        assert_eq!("User rejected. (code 5000)", e.to_string());
+
+        let e = PeerError::from(json!({"code": -32000, "message": "oops", "data": [1]}));
+        assert_eq!(Some(ErrorCode::Unknown(-32000)), e.code);
+        assert_eq!(None, e.sdk());
+        assert_eq!(Some(json!([1])), e.data);
+
+        let e = PeerError::from(json!({"code": 11000, "message": "Busy"}));
+        assert_eq!(Some(OverloadErrors::Busy), e.overload());
+        assert_eq!(None, e.sdk());
+
+        let e = PeerError::from(json!({"message": ""}));
+        assert_eq!(None, e.code);
+        assert_eq!(PeerError::from(json!("gone")).message, r#""gone""#);
+    }
+}
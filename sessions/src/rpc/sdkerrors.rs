@@ -1,5 +1,7 @@
 use crate::rpc::{ErrorParams, PairDeleteRequest};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(strum_macros::EnumIter))]
 pub enum SdkErrors {
     InvalidMethod,
     InvalidEvent,
@@ -52,6 +54,41 @@ impl From<SdkErrors> for SdkError<'_> {
     }
 }
 
+impl SdkErrors {
+    pub fn code(self) -> i64 {
+        SdkError::from(self).code
+    }
+
+    /// The variant that is sent with `code`.
+    pub const fn from_code(code: i64) -> Option<Self> {
+        let e = match code {
+            1001 => Self::InvalidMethod,
+            1002 => Self::InvalidEvent,
+            1003 => Self::InvalidUpdateRequest,
+            1004 => Self::InvalidExtendRequest,
+            1005 => Self::InvalidSessionSettleRequest,
+            3001 => Self::UnauthorizedMethod,
+            3002 => Self::UnauthorizedEvent,
+            3003 => Self::UnauthorizedUpdateRequest,
+            3004 => Self::UnauthorizedExtendRequest,
+            5000 => Self::UserRejected,
+            5001 => Self::UserRejectedChains,
+            5002 => Self::UserRejectedMethods,
+            5003 => Self::UserRejectedEvents,
+            5100 => Self::UnsupportedChains,
+            5101 => Self::UnsupportedMethods,
+            5102 => Self::UnsupportedEvents,
+            5103 => Self::UnsupportedAccounts,
+            5104 => Self::UnsupportedNamespaceKey,
+            6000 => Self::UserDisconnected,
+            7000 => Self::SessionSettlementFailed,
+            10001 => Self::WcMethodUnsupported,
+            _ => return None,
+        };
+        Some(e)
+    }
+}
+
 pub struct SdkError<'a> {
     pub code: i64,
     pub message: &'a str,
@@ -168,3 +205,16 @@ pub const WC_METHOD_UNSUPPORTED: SdkError = SdkError {
     message: "Unsupported wc_ method.",
     code: 10001,
 };
+
+#[cfg(test)]
+mod test {
+    use {super::*, strum::IntoEnumIterator};
+
+    #[test]
+    fn code_round_trip() {
+        for e in SdkErrors::iter() {
+            assert_eq!(Some(e), SdkErrors::from_code(e.code()), "{e:?}");
+        }
+        assert_eq!(None, SdkErrors::from_code(0));
+    }
+}
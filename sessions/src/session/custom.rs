@@ -0,0 +1,38 @@
+use {
+    crate::{
+        rpc::{
+            CustomRequest,
+            CustomResponse,
+            ResponseParamsError,
+            ResponseParamsSuccess,
+            RpcResponsePayload,
+            SdkErrors,
+        },
+        ClientSession,
+    },
+    xtra::prelude::*,
+};
+
+impl Handler<CustomRequest> for ClientSession {
+    type Return = RpcResponsePayload;
+
+    async fn handle(&mut self, message: CustomRequest, _ctx: &mut Context<Self>) -> Self::Return {
+        let irn = message.metadata.response;
+        let handler = self
+            .custom_handlers
+            .get(&message.method)
+            .map(|h| h.value().clone());
+        let result = match handler {
+            Some(handler) => handler.call(message.params).await,
+            None => Err(SdkErrors::UnsupportedMethods.into()),
+        };
+        match result {
+            Ok(v) => RpcResponsePayload::Success(ResponseParamsSuccess::Custom(
+                CustomResponse::new(v, irn),
+            )),
+            Err(e) => {
+                RpcResponsePayload::Error(ResponseParamsError::Custom(CustomResponse::new(e, irn)))
+            }
+        }
+    }
+}
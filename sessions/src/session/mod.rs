@@ -1,17 +1,38 @@
 use {
     crate::{
-        rpc::{RequestParams, SessionDeleteRequest},
+        actors::Actors,
+        custom::{CustomHandler, CustomMethod, ErasedHandler, TypedHandler},
+        eip155::Eip155Client,
+        lifecycle::Lifecycle,
+        policy::SessionPolicy,
+        rpc::{
+            CustomMetadata,
+            CustomRequest,
+            RequestMethod,
+            RequestParams,
+            SessionDeleteRequest,
+            SessionRequestRequest,
+        },
+        solana::SolanaClient,
+        tasks::Tasks,
         transport::SessionTransport,
         Error,
         Result,
+        SessionDeleteHandler,
         SessionHandler,
         Topic,
     },
+    dashmap::DashMap,
     monedero_domain::SessionSettled,
     serde::de::DeserializeOwned,
     std::{
         fmt::{Debug, Display, Formatter},
-        sync::Arc,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+            PoisonError,
+            RwLock,
+        },
         time::Duration,
     },
     tokio::sync::Mutex,
@@ -19,17 +40,41 @@ use {
     xtra::prelude::*,
 };
 
+mod custom;
 mod pending;
 mod session_delete;
 mod session_ping;
 mod session_request;
 
-pub(crate) use pending::PendingSession;
 use {
     crate::actors::{ClearSession, SessionRequestHandlerActor},
     monedero_cipher::CipherError,
-    monedero_domain::namespaces::Namespaces,
+    monedero_domain::namespaces::{
+        AlloyChain,
+        ChainId,
+        ChainType,
+        Method,
+        NamespaceName,
+        Namespaces,
+    },
+    serde_json::Value,
 };
+pub(crate) use {pending::PendingSession, session_delete::SessionClosed};
+
+/// Settlement of a session, changed by the peer's `wc_sessionUpdate` and
+/// `wc_sessionExtend`.
+pub(crate) type SharedSettlement = Arc<RwLock<SessionSettled>>;
+
+/// What the session actor needs to check and apply the peer's updates,
+/// without going through the mailbox of the session.
+#[derive(Clone)]
+pub(crate) struct SessionState {
+    pub settled: SharedSettlement,
+    /// Namespaces the dapp asked for, an update has to keep them
+    pub required: Arc<Namespaces>,
+    /// This side settled the session, so the peer cannot update it
+    pub controller: bool,
+}
 
 #[derive(Clone, Hash, Eq, PartialEq)]
 pub(crate) enum Category {
@@ -63,11 +108,18 @@ impl Display for Category {
 /// New session as the result of successful session proposal.
 #[derive(Clone, Actor)]
 pub struct ClientSession {
-    pub settled: Arc<SessionSettled>,
+    settled: SharedSettlement,
+    required: Arc<Namespaces>,
     transport: SessionTransport,
     session_actor: Address<SessionRequestHandlerActor>,
     handler: Arc<Mutex<Box<dyn SessionHandler>>>,
+    policy: Option<Arc<SessionPolicy>>,
+    delete_handlers: Arc<Mutex<Vec<Box<dyn SessionDeleteHandler>>>>,
+    custom_handlers: Arc<DashMap<String, Arc<dyn ErasedHandler>>>,
+    closed: Arc<AtomicBool>,
     category: Category,
+    lifecycle: Lifecycle,
+    tasks: Tasks,
 }
 
 impl Debug for ClientSession {
@@ -83,18 +135,27 @@ impl Debug for ClientSession {
 
 impl ClientSession {
     pub(crate) async fn new(
-        session_actor: Address<SessionRequestHandlerActor>,
+        actors: &Actors,
         transport: SessionTransport,
         settled: SessionSettled,
+        required: Namespaces,
         handler: Arc<Mutex<Box<dyn SessionHandler>>>,
+        policy: Option<SessionPolicy>,
         category: Category,
     ) -> Result<Self> {
         let me = Self {
-            session_actor,
+            session_actor: actors.session(),
             transport,
-            settled: Arc::new(settled),
+            settled: Arc::new(RwLock::new(settled)),
+            required: Arc::new(required),
             handler,
+            policy: policy.map(Arc::new),
+            delete_handlers: Arc::new(Mutex::new(Vec::new())),
+            custom_handlers: Arc::new(DashMap::new()),
+            closed: Arc::new(AtomicBool::new(false)),
             category,
+            lifecycle: actors.lifecycle(),
+            tasks: actors.tasks(),
         };
         me.register().await?;
         Ok(me)
@@ -107,15 +168,125 @@ impl ClientSession {
         Ok(())
     }
 
-    pub fn namespaces(&self) -> &Namespaces {
-        &self.settled.namespaces
+    /// The settlement as last updated or extended by the peer.
+    pub fn settled(&self) -> SessionSettled {
+        self.settled
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn namespaces(&self) -> Namespaces {
+        self.settled
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .namespaces
+            .clone()
+    }
+
+    pub(crate) fn state(&self) -> SessionState {
+        SessionState {
+            settled: self.settled.clone(),
+            required: self.required.clone(),
+            controller: self.category == Category::Wallet,
+        }
     }
 
     pub fn topic(&self) -> Topic {
         self.transport.topic.clone()
     }
 
+    pub(crate) fn lifecycle(&self) -> Lifecycle {
+        self.lifecycle.clone()
+    }
+
+    pub(crate) fn tasks(&self) -> Tasks {
+        self.tasks.clone()
+    }
+
+    /// Call `handler` when the peer deletes this session.
+    pub async fn on_delete<T: SessionDeleteHandler>(&self, handler: T) {
+        self.delete_handlers.lock().await.push(Box::new(handler));
+    }
+
+    /// Answer the peer's requests of the custom method `M` with `handler`,
+    /// replacing any handler set before. Without one they are rejected with
+    /// [`crate::rpc::SdkErrors::UnsupportedMethods`].
+    pub fn on_request<M: CustomMethod>(&self, handler: impl CustomHandler<M>) {
+        self.custom_handlers.insert(
+            String::from(M::METHOD),
+            Arc::new(TypedHandler::<M, _>::new(handler)),
+        );
+    }
+
+    /// Call the custom method `M` on the peer. `M` must be registered with
+    /// [`crate::ReownBuilder::custom_method`].
+    pub async fn request<M: CustomMethod>(&self, params: M::Params) -> Result<M::Result> {
+        let request = CustomRequest {
+            method: String::from(M::METHOD),
+            params: serde_json::to_value(params)?,
+            metadata: CustomMetadata {
+                request: M::REQUEST,
+                response: M::RESPONSE,
+            },
+        };
+        self.publish_request(RequestParams::Custom(request)).await
+    }
+
+    /// Send `method` to the wallet as `wc_sessionRequest` on `chain`, if the
+    /// namespaces as last updated by the wallet allow it.
+    pub async fn chain_request<R: DeserializeOwned>(
+        &self,
+        chain: ChainId,
+        method: Method,
+        params: Value,
+    ) -> Result<R> {
+        let settled = self
+            .settled
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .namespaces
+            .get(&NamespaceName::from(&chain))
+            .is_some_and(|ns| ns.chains.contains(&chain) && ns.methods.0.contains(&method));
+        if !settled {
+            return Err(Error::MethodNotSettled(method, chain));
+        }
+        let request = SessionRequestRequest {
+            request: RequestMethod {
+                method,
+                params,
+                expiry: None,
+            },
+            chain_id: chain,
+        };
+        self.publish_request(RequestParams::SessionRequest(request))
+            .await
+    }
+
+    /// Typed EIP-155 requests on `chain`.
+    pub const fn eip155(&self, chain: AlloyChain) -> Eip155Client<'_> {
+        Eip155Client::new(self, chain)
+    }
+
+    /// Typed Solana requests on `chain`.
+    pub const fn solana(&self, chain: ChainType) -> SolanaClient<'_> {
+        SolanaClient::new(self, chain)
+    }
+
+    /// True once the session was deleted by either side or has expired.
+    /// Requests on a closed session fail with [`Error::NoClientSession`].
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+
     pub async fn publish_request<R: DeserializeOwned>(&self, params: RequestParams) -> Result<R> {
+        if self.is_closed() {
+            return Err(Error::NoClientSession(self.topic()));
+        }
         match self.transport.publish_request(params).await {
             Ok(r) => Ok(r),
             Err(Error::CipherError(CipherError::UnknownTopic(_))) => {
@@ -144,13 +315,20 @@ impl ClientSession {
                 false
             }
         };
+        self.clear().await;
+        accepted
+    }
+
+    /// Forget the session without telling the peer.
+    pub(crate) async fn clear(&self) {
+        self.close();
         let _ = self
             .session_actor
             .send(ClearSession(self.transport.topic.clone()))
             .await;
-        accepted
     }
 
+    #[deprecated(note = "use `ClientSession::heartbeat`, which reports unreachable peers")]
     pub async fn pinger(&self, duration: Duration) {
         let me = self.clone();
         loop {
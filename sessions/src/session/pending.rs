@@ -1,5 +1,6 @@
 use {
     crate::{
+        policy::SessionPolicy,
         rpc::{RequestParams, SessionSettleRequest},
         session::Category,
         transport::SessionTransport,
@@ -10,7 +11,7 @@ use {
         SessionHandler,
     },
     dashmap::DashMap,
-    monedero_domain::{PairingTopic, SessionSettled},
+    monedero_domain::{namespaces::Namespaces, PairingTopic, SessionSettled},
     std::sync::Arc,
     tokio::sync::{
         oneshot::{self, Sender},
@@ -22,6 +23,8 @@ use {
 pub struct HandlerContainer {
     pub tx: Sender<Result<ClientSession>>,
     pub handlers: Arc<Mutex<Box<dyn SessionHandler>>>,
+    /// Namespaces the dapp asked for
+    pub required: Namespaces,
 }
 
 #[derive(Clone, Default)]
@@ -38,11 +41,13 @@ impl PendingSession {
         &self,
         topic: PairingTopic,
         handlers: T,
+        required: Namespaces,
     ) -> oneshot::Receiver<Result<ClientSession>> {
         let (tx, rx) = oneshot::channel::<Result<ClientSession>>();
         let h = HandlerContainer {
             tx,
             handlers: Arc::new(Mutex::new(Box::new(handlers))),
+            required,
         };
         self.pending.insert(topic, h);
         rx
@@ -72,6 +77,7 @@ impl PendingSession {
         settled: SessionSettled,
         category: Category,
         send_to_peer: Option<SessionSettleRequest>,
+        policy: Option<SessionPolicy>,
     ) -> Result<ClientSession> {
         let pairing_topic = mgr.topic().ok_or(Error::NoPairingTopic)?;
         let handlers = self.remove(&pairing_topic)?;
@@ -81,10 +87,12 @@ impl PendingSession {
             transport: mgr.topic_transport(),
         };
         let client_session = ClientSession::new(
-            actors.session(),
+            &actors,
             session_transport,
             settled.clone(),
+            handlers.required,
             handlers.handlers,
+            policy,
             category,
         )
         .await?;
@@ -97,7 +105,10 @@ impl PendingSession {
                 .publish_request::<bool>(RequestParams::SessionSettle(req))
                 .await;
             let client_session_result: Result<ClientSession> = match result {
-                Ok(true) => Ok(client_session.clone()),
+                Ok(true) => {
+                    actors.lifecycle().emit((&settled).into());
+                    Ok(client_session.clone())
+                }
                 Ok(false) => Err(Error::ProposalRejected),
                 Err(e) => Err(e),
             };
@@ -105,6 +116,7 @@ impl PendingSession {
                 warn!("oneshot proposal channel has closed");
             }
         } else {
+            actors.lifecycle().emit((&settled).into());
             handlers
                 .tx
                 .send(Ok(client_session.clone()))
@@ -1,17 +1,16 @@
 use {
-    crate::{rpc::SessionDeleteRequest, ClientSession, SessionDeleteHandler},
-    tokio::sync::mpsc,
-    tracing::info,
+    crate::{rpc::SessionDeleteRequest, ClientSession},
     xtra::prelude::*,
 };
 
-#[allow(dead_code)]
-pub async fn handle_delete<T: SessionDeleteHandler>(
-    handler: T,
-    mut rx: mpsc::Receiver<SessionDeleteRequest>,
-) {
-    while let Some(message) = rx.recv().await {
-        handler.handle(message).await;
+/// The session is gone, sent by the session actor on delete or expiry.
+pub struct SessionClosed;
+
+impl Handler<SessionClosed> for ClientSession {
+    type Return = ();
+
+    async fn handle(&mut self, _message: SessionClosed, _ctx: &mut Context<Self>) -> Self::Return {
+        self.close();
     }
 }
 
@@ -23,6 +22,10 @@ impl Handler<SessionDeleteRequest> for ClientSession {
         message: SessionDeleteRequest,
         _ctx: &mut Context<Self>,
     ) -> Self::Return {
-        info!("session delete requested {message:#?}");
+        let topic = self.topic();
+        let handlers = self.delete_handlers.lock().await;
+        for handler in handlers.iter() {
+            handler.handle(topic.clone(), message.clone()).await;
+        }
     }
 }
@@ -20,7 +20,10 @@ impl Handler<SessionRequestRequest> for ClientSession {
         message: SessionRequestRequest,
         _ctx: &mut Context<Self>,
     ) -> Self::Return {
-        let result = self.handler.lock().await.request(message).await;
+        let result = match &self.policy {
+            Some(policy) => policy.request(message, &self.handler).await,
+            None => self.handler.lock().await.request(message).await,
+        };
         match result {
             WalletRequestResponse::Success(v) => {
                 RpcResponsePayload::Success(ResponseParamsSuccess::SessionRequest(v))
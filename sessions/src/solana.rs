@@ -0,0 +1,248 @@
+//! Typed client for the Solana methods of [`SolanaMethod`].
+//!
+//! https://docs.walletconnect.com/advanced/multichain/rpc-reference/solana-rpc
+
+use {
+    crate::{ClientSession, Error, Result},
+    base64::{engine::general_purpose::STANDARD, Engine},
+    monedero_domain::namespaces::{ChainId, ChainType, Method, SolanaMethod},
+    serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer},
+    serde_json::{json, Value},
+    std::{
+        fmt::{Display, Formatter},
+        str::FromStr,
+    },
+};
+
+/// How serialized transactions go over the wire.
+///
+/// Messages are always base58, transactions are base64 unless the wallet
+/// only understands the older base58 form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    Base58,
+    #[default]
+    Base64,
+}
+
+impl Encoding {
+    #[must_use]
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Base58 => bs58::encode(bytes).into_string(),
+            Self::Base64 => STANDARD.encode(bytes),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn decode(self, value: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Base58 => bs58::decode(value)
+                .into_vec()
+                .map_err(|e| Error::InvalidEncoding(e.to_string())),
+            Self::Base64 => STANDARD
+                .decode(value)
+                .map_err(|e| Error::InvalidEncoding(e.to_string())),
+        }
+    }
+}
+
+/// Ed25519 signature, base58 on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature(pub [u8; 64]);
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", bs58::encode(self.0).into_string())
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = Encoding::Base58.decode(s)?;
+        let sig = <[u8; 64]>::try_from(bytes.as_slice())
+            .map_err(|_| Error::InvalidEncoding(format!("signature is {} bytes", bytes.len())))?;
+        Ok(Self(sig))
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Options of `solana_signAndSendTransaction`, passed on to `sendTransaction`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendOptions {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub skip_preflight: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub preflight_commitment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_retries: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub min_context_slot: Option<u64>,
+}
+
+/// Result of `solana_signTransaction`.
+///
+/// Wallets following the newer spec also return the signed transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub signature: Signature,
+    pub transaction: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct SignatureResponse {
+    signature: Signature,
+    #[serde(default)]
+    transaction: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TransactionsResponse {
+    transactions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AccountResponse {
+    pubkey: String,
+}
+
+/// Requests to the wallet on one Solana cluster, see
+/// [`ClientSession::solana`].
+///
+/// Every request fails with [`crate::Error::MethodNotSettled`] unless the
+/// session namespaces, as last updated by the wallet, allow the method on
+/// this cluster.
+pub struct SolanaClient<'a> {
+    session: &'a ClientSession,
+    chain: ChainId,
+    encoding: Encoding,
+}
+
+impl<'a> SolanaClient<'a> {
+    pub(crate) const fn new(session: &'a ClientSession, chain: ChainType) -> Self {
+        Self {
+            session,
+            chain: ChainId::Solana(chain),
+            encoding: Encoding::Base64,
+        }
+    }
+
+    /// Encoding of serialized transactions sent and received, base64 by
+    /// default.
+    #[must_use]
+    pub const fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    async fn send<R: DeserializeOwned>(&self, method: SolanaMethod, params: Value) -> Result<R> {
+        self.session
+            .chain_request(self.chain.clone(), Method::Solana(method), params)
+            .await
+    }
+
+    /// `solana_getAccounts`, the base58 public keys of the wallet.
+    pub async fn accounts(&self) -> Result<Vec<String>> {
+        let accounts: Vec<AccountResponse> =
+            self.send(SolanaMethod::GetAccounts, json!({})).await?;
+        Ok(accounts.into_iter().map(|a| a.pubkey).collect())
+    }
+
+    /// `solana_signMessage`, `pubkey` is the base58 account signing.
+    pub async fn sign_message(&self, message: &[u8], pubkey: &str) -> Result<Signature> {
+        let params = json!({
+            "message": Encoding::Base58.encode(message),
+            "pubkey": pubkey,
+        });
+        let response: SignatureResponse = self.send(SolanaMethod::SignMessage, params).await?;
+        Ok(response.signature)
+    }
+
+    /// `solana_signTransaction` of a serialized transaction.
+    #[allow(clippy::result_large_err)]
+    pub async fn sign_transaction(&self, transaction: &[u8]) -> Result<SignedTransaction> {
+        let params = json!({ "transaction": self.encoding.encode(transaction) });
+        let response: SignatureResponse = self.send(SolanaMethod::SignTransaction, params).await?;
+        Ok(SignedTransaction {
+            signature: response.signature,
+            transaction: response
+                .transaction
+                .map(|t| self.encoding.decode(&t))
+                .transpose()?,
+        })
+    }
+
+    /// `solana_signAllTransactions`, returns the signed transactions in
+    /// order.
+    #[allow(clippy::result_large_err)]
+    pub async fn sign_all_transactions<T: AsRef<[u8]> + Sync>(
+        &self,
+        transactions: &[T],
+    ) -> Result<Vec<Vec<u8>>> {
+        let encoded = transactions
+            .iter()
+            .map(|t| self.encoding.encode(t.as_ref()))
+            .collect::<Vec<_>>();
+        let params = json!({ "transactions": encoded });
+        let response: TransactionsResponse =
+            self.send(SolanaMethod::SignAllTransactions, params).await?;
+        response
+            .transactions
+            .iter()
+            .map(|t| self.encoding.decode(t))
+            .collect()
+    }
+
+    /// `solana_signAndSendTransaction`, returns the transaction signature.
+    pub async fn sign_and_send_transaction(
+        &self,
+        transaction: &[u8],
+        options: Option<SendOptions>,
+    ) -> Result<Signature> {
+        let mut params = json!({ "transaction": self.encoding.encode(transaction) });
+        if let Some(options) = options {
+            params["sendOptions"] = serde_json::to_value(options)?;
+        }
+        let response: SignatureResponse = self
+            .send(SolanaMethod::SignAndSendTransaction, params)
+            .await?;
+        Ok(response.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() -> anyhow::Result<()> {
+        let bytes = b"hello world";
+        assert_eq!("StV1DL6CwTryKyV", Encoding::Base58.encode(bytes));
+        assert_eq!("aGVsbG8gd29ybGQ=", Encoding::Base64.encode(bytes));
+        for encoding in [Encoding::Base58, Encoding::Base64] {
+            assert_eq!(bytes.to_vec(), encoding.decode(&encoding.encode(bytes))?);
+        }
+        assert!(Encoding::Base58.decode("0OIl").is_err());
+
+        let sig = Signature([7; 64]);
+        let json = serde_json::to_value(sig)?;
+        assert_eq!(sig, serde_json::from_value(json)?);
+        assert!(serde_json::from_value::<Signature>(json!("StV1DL6CwTryKyV")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use {
+    crate::spawn_task,
+    std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+    tokio::sync::{watch, Notify},
+};
+
+#[derive(Default)]
+struct Running {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+struct TaskGuard(Arc<Running>);
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Background tasks of one [`crate::PairingManager`].
+///
+/// [`Tasks::spawn`] tasks are dropped on [`Tasks::cancel`], while
+/// [`Tasks::spawn_flushed`] tasks are left to finish. [`Tasks::wait`]
+/// resolves once both kinds are done.
+#[derive(Clone)]
+pub struct Tasks {
+    cancel: watch::Sender<bool>,
+    running: Arc<Running>,
+}
+
+impl Default for Tasks {
+    fn default() -> Self {
+        let (cancel, _) = watch::channel(false);
+        Self {
+            cancel,
+            running: Arc::new(Running::default()),
+        }
+    }
+}
+
+impl Tasks {
+    fn guarded<F>(&self, future: F, cancellable: bool) -> impl Future<Output = ()>
+    where
+        F: Future<Output = ()>,
+    {
+        self.running.count.fetch_add(1, Ordering::AcqRel);
+        let guard = TaskGuard(self.running.clone());
+        let mut cancelled = self.cancel.subscribe();
+        async move {
+            let _guard = guard;
+            if cancellable {
+                tokio::select! {
+                    () = future => {}
+                    _ = cancelled.wait_for(|c| *c) => {}
+                }
+            } else {
+                future.await;
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        spawn_task(self.guarded(future, true));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        spawn_task(self.guarded(future, true));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn_flushed<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        spawn_task(self.guarded(future, false));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn spawn_flushed<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        spawn_task(self.guarded(future, false));
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    /// Resolves once [`Tasks::cancel`] has been called.
+    pub async fn cancelled(&self) {
+        let _ = self.cancel.subscribe().wait_for(|c| *c).await;
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.count.load(Ordering::Acquire)
+    }
+
+    /// Resolves once no task is running.
+    pub async fn wait(&self) {
+        loop {
+            let idle = self.running.idle.notified();
+            if self.running() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, std::time::Duration};
+
+    #[tokio::test]
+    async fn tasks_cancel_and_flush() {
+        let tasks = Tasks::default();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tasks.spawn(std::future::pending());
+        tasks.spawn_flushed(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = tx.send(());
+        });
+        assert_eq!(2, tasks.running());
+        tasks.cancel();
+        tokio::time::timeout(Duration::from_secs(1), tasks.wait())
+            .await
+            .expect("tasks did not finish");
+        assert!(rx.try_recv().is_ok());
+        assert!(tasks.is_cancelled());
+    }
+}
@@ -43,7 +43,7 @@ impl TopicTransport {
             return match result {
                 Ok(response) => match response.params {
                     ResponseParams::Success(v) => Ok(serde_json::from_value(v)?),
-                    ResponseParams::Err(v) => Err(crate::Error::RpcError(v)),
+                    ResponseParams::Err(v) => Err(crate::Error::RpcError(v.into())),
                 },
                 Err(_) => Err(crate::Error::ResponseChannelError(id)),
             };
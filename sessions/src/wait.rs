@@ -34,3 +34,13 @@ where
         Either::Right(_) => Err(WaitError(duration_ms)),
     }
 }
+
+#[cfg(not(target_family = "wasm"))]
+pub async fn sleep(duration: std::time::Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(target_family = "wasm")]
+pub async fn sleep(duration: std::time::Duration) {
+    gloo_timers::future::sleep(duration).await;
+}
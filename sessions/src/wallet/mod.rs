@@ -3,6 +3,8 @@ mod settlement;
 use {
     crate::{
         actors::actor_spawn,
+        lifecycle::SessionLifecycleEvent,
+        policy::{PolicyEngine, SessionPolicy},
         rpc::{
             Controller,
             Metadata,
@@ -22,11 +24,11 @@ use {
         SessionHandler,
         WalletSettlementHandler,
     },
-    monedero_domain::{Pairing, SessionSettled},
+    monedero_domain::{namespaces::Namespaces, Pairing, SessionSettled},
     std::{
         fmt::{Debug, Display, Formatter},
         str::FromStr,
-        sync::Arc,
+        sync::{Arc, PoisonError, RwLock},
     },
     tracing::{error, warn},
     xtra::prelude::*,
@@ -38,6 +40,7 @@ pub struct Wallet {
     pending: Arc<PendingSession>,
     settlement_handler: Address<WalletSettlementActor>,
     metadata: Metadata,
+    policy: Arc<RwLock<Option<Arc<PolicyEngine>>>>,
 }
 
 impl Display for Wallet {
@@ -63,6 +66,10 @@ impl Wallet {
             .manager
             .register_dapp_pk(request.proposer.clone())
             .await?;
+        let policy = self.policy().map(|engine| SessionPolicy {
+            engine,
+            peer: request.proposer.metadata.clone(),
+        });
         let namespaces = self.settlement_handler.send(request).await??;
         let now = chrono::Utc::now();
         let future = now + chrono::Duration::hours(24);
@@ -85,6 +92,7 @@ impl Wallet {
                 },
                 Category::Wallet,
                 Some(session_settlement),
+                policy,
             )
             .await?;
         Ok(())
@@ -113,6 +121,15 @@ impl Handler<SessionProposeRequest> for Wallet {
             ));
         }
         let pk = pk.unwrap();
+        if let Some(pairing_topic) = self.manager.topic() {
+            self.manager
+                .actors()
+                .lifecycle()
+                .emit(SessionLifecycleEvent::Proposed {
+                    pairing_topic,
+                    required_namespaces: message.required_namespaces.clone(),
+                });
+        }
         if let Ok((accepted, response)) = self
             .settlement_handler
             .send(SessionProposePublicKey(String::from(&pk), message.clone()))
@@ -153,11 +170,28 @@ impl Wallet {
             pending: Arc::new(PendingSession::new()),
             metadata,
             settlement_handler,
+            policy: Arc::new(RwLock::new(None)),
         };
         me.manager.actors().proposal().send(me.clone()).await?;
         Ok(me)
     }
 
+    /// Answer `wc_sessionRequest` with `engine` before asking the session
+    /// handler. Applies to sessions settled from now on.
+    #[allow(clippy::result_large_err)]
+    pub fn set_policy(&self, engine: PolicyEngine) -> Result<()> {
+        engine.check()?;
+        *self.policy.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(engine));
+        Ok(())
+    }
+
+    fn policy(&self) -> Option<Arc<PolicyEngine>> {
+        self.policy
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
     #[tracing::instrument(skip(handlers), level = "info")]
     pub async fn pair<T: SessionHandler>(
         &self,
@@ -165,7 +199,11 @@ impl Wallet {
         handlers: T,
     ) -> Result<(Pairing, ProposeFuture)> {
         let pairing = Pairing::from_str(&uri)?;
-        let rx = self.pending.add(pairing.topic.clone(), handlers);
+        // the wallet is the controller and takes no updates, so it does not
+        // check against the required namespaces
+        let rx = self
+            .pending
+            .add(pairing.topic.clone(), handlers, Namespaces::default());
         self.manager.set_pairing(pairing.clone()).await?;
         Ok((pairing, ProposeFuture::new(rx)))
     }
@@ -1,51 +1,109 @@
 use {
+    alloy_primitives::{Address, Bytes, B256},
     anyhow::format_err,
     assert_matches::assert_matches,
-    monedero_domain::namespaces::{AlloyChain, ChainId, ChainType, NamespaceName},
-    monedero_mesh::{ClientSession, NoopSessionHandler, ProposeFuture, RegisteredComponents},
-    std::time::Duration,
-    tokio::time::timeout,
+    async_trait::async_trait,
+    monedero_domain::{
+        namespaces::{
+            AlloyChain,
+            ChainId,
+            ChainType,
+            EipMethod,
+            Method,
+            NamespaceName,
+            Namespaces,
+            SolanaMethod,
+        },
+        Topic,
+    },
+    monedero_mesh::{
+        eip155::{Call, CallsStatus, SendCalls, TransactionRequest},
+        rpc::{
+            ErrorCode,
+            ErrorParams,
+            IrnMetadata,
+            OverloadErrors,
+            PairDeleteRequest,
+            RequestMethod,
+            RequestParams,
+            Response,
+            RpcRequest,
+            SdkErrors,
+            SessionDeleteRequest,
+            SessionExtendRequest,
+            SessionRequestRequest,
+            SessionUpdateRequest,
+        },
+        solana::{Encoding, SendOptions, Signature},
+        ClientSession,
+        CustomHandler,
+        CustomMethod,
+        Flow,
+        HeartbeatConfig,
+        Middleware,
+        NoopSessionHandler,
+        PairDeleteHandler,
+        Policy,
+        PolicyEngine,
+        QueueLimits,
+        RateLimit,
+        RateLimits,
+        RegisteredComponents,
+        ReownBuilder,
+        SessionDeleteHandler,
+        SessionEventHandler,
+        SessionHandler,
+        SessionLifecycleEvent,
+        ShutdownOptions,
+        WalletRequestResponse,
+    },
+    serde_json::{json, Value},
+    std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::{
+        sync::{broadcast, mpsc, Semaphore},
+        time::timeout,
+    },
     tracing::{error, info},
 };
 
 mod test_utils;
 use test_utils::*;
 
-async fn await_wallet_pair(rx: ProposeFuture) {
-    match timeout(Duration::from_secs(5), rx).await {
-        Ok(s) => match s {
-            Ok(_) => {
-                info!("wallet got client session");
-            }
-            Err(e) => error!("wallet got error session: {e}"),
-        },
-        Err(e) => error!("timeout for wallet to recv client session from wallet: {e}"),
-    }
+fn all_chains() -> [ChainId; 3] {
+    [
+        ChainId::EIP155(AlloyChain::holesky()),
+        ChainId::EIP155(AlloyChain::sepolia()),
+        ChainId::Solana(ChainType::Dev),
+    ]
 }
 
-async fn pair_dapp_wallet() -> anyhow::Result<(TestStuff, ClientSession)> {
-    let t = init_test_components().await?;
-    let dapp = t.dapp.clone();
-    let wallet = t.wallet.clone();
-    let (pairing, rx, _) = dapp
-        .propose(NoopSessionHandler, &[
-            ChainId::EIP155(alloy_chains::Chain::holesky()),
-            ChainId::EIP155(alloy_chains::Chain::sepolia()),
-            ChainId::Solana(ChainType::Dev),
-        ])
-        .await?;
+/// Pair the dapp and the wallet of `t` on a new pairing, the wallet answers
+/// session requests with `handler`. Returns the dapp's and the wallet's
+/// session.
+async fn pair(
+    t: &TestStuff,
+    chains: impl Into<Namespaces> + Send,
+    handler: impl SessionHandler,
+) -> anyhow::Result<(ClientSession, ClientSession)> {
+    let (pairing, rx, _) = t.dapp.propose(NoopSessionHandler, chains).await?;
     info!("got pairing topic {pairing}");
-    let (_, wallet_rx) = wallet.pair(pairing.to_string(), NoopSessionHandler).await?;
-    tokio::spawn(async move { await_wallet_pair(wallet_rx).await });
+    let (_, wallet_rx) = t.wallet.pair(pairing.to_string(), handler).await?;
     let session = timeout(Duration::from_secs(5), rx).await??;
-    // let wallet get their ClientSession
-    yield_ms(1000).await;
-    Ok((t, session))
+    let wallet_session = timeout(Duration::from_secs(5), wallet_rx).await??;
+    yield_ms(500).await;
+    Ok((session, wallet_session))
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 10)]
 async fn test_dapp_settlement() -> anyhow::Result<()> {
-    let (test, session) = pair_dapp_wallet().await?;
+    let test = init_test_components().await?;
+    let (session, _) = pair(&test, &all_chains(), NoopSessionHandler).await?;
     info!("settlement complete");
     assert!(session.namespaces().contains_key(&NamespaceName::Solana));
     assert!(session.ping().await?);
@@ -94,3 +152,912 @@ async fn test_dapp_settlement() -> anyhow::Result<()> {
     yield_ms(5000).await;
     Ok(())
 }
+
+async fn next_event<T: Clone>(rx: &mut broadcast::Receiver<T>) -> anyhow::Result<T> {
+    Ok(timeout(Duration::from_secs(5), rx.recv()).await??)
+}
+
+/// Next heartbeat event of the lifecycle stream.
+async fn next_heartbeat_event(
+    rx: &mut broadcast::Receiver<SessionLifecycleEvent>,
+) -> anyhow::Result<SessionLifecycleEvent> {
+    loop {
+        let event = next_event(rx).await?;
+        if matches!(
+            event,
+            SessionLifecycleEvent::Unreachable { .. }
+                | SessionLifecycleEvent::Recovered { .. }
+                | SessionLifecycleEvent::TornDown { .. }
+        ) {
+            return Ok(event);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_session_heartbeat() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let (session, _) = pair(&test, &all_chains(), NoopSessionHandler).await?;
+    let topic = session.topic();
+    let mut events = test.dapp_manager.lifecycle();
+    let heartbeat = session.heartbeat(HeartbeatConfig {
+        interval: Duration::from_millis(100),
+        timeout: Duration::from_millis(300),
+        failure_threshold: 2,
+        teardown_after: Some(6),
+    })?;
+    yield_ms(300).await;
+    assert!(heartbeat.is_reachable());
+
+    test.wallet_manager.disconnect_socket().await?;
+    assert_eq!(
+        SessionLifecycleEvent::Unreachable {
+            topic: topic.clone(),
+            failures: 2
+        },
+        next_heartbeat_event(&mut events).await?
+    );
+    assert!(!heartbeat.is_reachable());
+    test.wallet_manager.reconnect().await?;
+    assert_eq!(
+        SessionLifecycleEvent::Recovered {
+            topic: topic.clone()
+        },
+        next_heartbeat_event(&mut events).await?
+    );
+    assert!(heartbeat.is_reachable());
+
+    test.wallet_manager.disconnect_socket().await?;
+    assert_matches!(
+        next_heartbeat_event(&mut events).await?,
+        SessionLifecycleEvent::Unreachable { .. }
+    );
+    assert_eq!(
+        SessionLifecycleEvent::TornDown { topic },
+        next_heartbeat_event(&mut events).await?
+    );
+    assert_matches!(
+        session.ping().await,
+        Err(monedero_mesh::Error::NoClientSession(_))
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_session_lifecycle() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let mut dapp_events = test.dapp_manager.lifecycle();
+    let mut wallet_events = test.wallet_manager.lifecycle();
+    let (session, _) = pair(&test, &all_chains(), NoopSessionHandler).await?;
+    let topic = session.topic();
+    let pairing_topic = test
+        .dapp
+        .pairing()
+        .ok_or_else(|| format_err!("no pairing!"))?
+        .topic;
+
+    for events in [&mut dapp_events, &mut wallet_events] {
+        assert_matches!(
+            next_event(events).await?,
+            SessionLifecycleEvent::Proposed { pairing_topic: t, .. } if t == pairing_topic
+        );
+        assert_matches!(
+            next_event(events).await?,
+            SessionLifecycleEvent::Settled { topic: t, namespaces, .. }
+                if t == topic && namespaces.contains_key(&NamespaceName::Solana)
+        );
+    }
+
+    assert!(session.delete().await);
+    assert_eq!(
+        SessionLifecycleEvent::DeletedByPeer {
+            topic: topic.clone()
+        },
+        next_event(&mut wallet_events).await?
+    );
+
+    test.dapp.purge().await?;
+    for events in [&mut dapp_events, &mut wallet_events] {
+        assert_eq!(
+            SessionLifecycleEvent::PairingDeleted {
+                pairing_topic: pairing_topic.clone()
+            },
+            next_event(events).await?
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_session_update_and_extend() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let mut dapp_events = test.dapp_manager.lifecycle();
+    let (session, wallet_session) = pair(&test, &all_chains(), NoopSessionHandler).await?;
+    let topic = session.topic();
+
+    let mut namespaces = session.namespaces();
+    let eip155 = namespaces
+        .get_mut(&NamespaceName::EIP155)
+        .ok_or_else(|| format_err!("no eip155 namespace"))?;
+    eip155.chains.0.insert(ChainId::EIP155(AlloyChain::mainnet()));
+    assert!(
+        wallet_session
+            .publish_request::<bool>(RequestParams::SessionUpdate(SessionUpdateRequest {
+                namespaces: namespaces.clone(),
+            }))
+            .await?
+    );
+    loop {
+        if let SessionLifecycleEvent::Updated {
+            topic: t,
+            namespaces: n,
+        } = next_event(&mut dapp_events).await?
+        {
+            assert_eq!(topic, t);
+            assert_eq!(namespaces, n);
+            break;
+        }
+    }
+    assert_eq!(namespaces, session.namespaces());
+    let stored = test.dapp_manager.ciphers().settlements()?;
+    assert_eq!(Some(&namespaces), stored.first().map(|s| &s.namespaces));
+
+    // only the wallet can update, and it has to keep the required chains
+    let mut dropped = namespaces.clone();
+    if let Some(eip155) = dropped.get_mut(&NamespaceName::EIP155) {
+        eip155.chains.0.remove(&ChainId::EIP155(AlloyChain::holesky()));
+    }
+    let update = |namespaces: Namespaces| {
+        RequestParams::SessionUpdate(SessionUpdateRequest { namespaces })
+    };
+    assert_matches!(
+        session.publish_request::<bool>(update(dropped.clone())).await,
+        Err(monedero_mesh::Error::RpcError(e))
+            if e.sdk() == Some(SdkErrors::UnauthorizedUpdateRequest)
+    );
+    assert_matches!(
+        wallet_session.publish_request::<bool>(update(dropped)).await,
+        Err(monedero_mesh::Error::RpcError(e))
+            if e.sdk() == Some(SdkErrors::InvalidUpdateRequest)
+    );
+    assert_eq!(namespaces, session.namespaces());
+
+    let expiry = session.settled().expiry;
+    let extend = |expiry: i64| {
+        RequestParams::SessionExtend(SessionExtendRequest {
+            expiry: u64::try_from(expiry).unwrap_or_default(),
+        })
+    };
+    for rejected in [expiry - 1, expiry + 8 * 24 * 60 * 60] {
+        assert_matches!(
+            wallet_session.publish_request::<bool>(extend(rejected)).await,
+            Err(monedero_mesh::Error::RpcError(e))
+                if e.sdk() == Some(SdkErrors::InvalidExtendRequest)
+        );
+    }
+    assert_eq!(expiry, session.settled().expiry);
+    assert!(wallet_session.publish_request::<bool>(extend(expiry + 3600)).await?);
+    assert_eq!(expiry + 3600, session.settled().expiry);
+    let stored = test.dapp_manager.ciphers().settlements()?;
+    assert_eq!(Some(expiry + 3600), stored.first().map(|s| s.expiry));
+    Ok(())
+}
+
+struct DeleteRecorder<T>(mpsc::UnboundedSender<(Topic, T)>);
+
+#[async_trait]
+impl SessionDeleteHandler for DeleteRecorder<SessionDeleteRequest> {
+    async fn handle(&self, topic: Topic, request: SessionDeleteRequest) {
+        let _ = self.0.send((topic, request));
+    }
+}
+
+#[async_trait]
+impl PairDeleteHandler for DeleteRecorder<PairDeleteRequest> {
+    async fn handle(&self, pairing_topic: Topic, request: PairDeleteRequest) {
+        let _ = self.0.send((pairing_topic, request));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_peer_delete() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let sepolia = [ChainId::EIP155(AlloyChain::sepolia())];
+    let (dapp_session, wallet_session) = pair(&test, &sepolia, NoopSessionHandler).await?;
+    let topic = dapp_session.topic();
+
+    let (tx, mut deleted) = mpsc::unbounded_channel();
+    dapp_session.on_delete(DeleteRecorder(tx)).await;
+    let (tx, mut pair_deleted) = mpsc::unbounded_channel();
+    test.dapp_manager
+        .register_pair_delete_handler(DeleteRecorder(tx))
+        .await;
+
+    assert!(wallet_session.delete().await);
+    assert!(wallet_session.is_closed());
+    let (deleted_topic, request) = timeout(Duration::from_secs(5), deleted.recv())
+        .await?
+        .ok_or_else(|| format_err!("delete handler dropped"))?;
+    assert_eq!(topic, deleted_topic);
+    assert_eq!(SessionDeleteRequest::default(), request);
+    assert!(dapp_session.is_closed());
+    assert_matches!(
+        timeout(Duration::from_millis(100), dapp_session.ping()).await?,
+        Err(monedero_mesh::Error::NoClientSession(_))
+    );
+
+    // the sessions of a deleted pairing are closed on both sides
+    let (dapp_session, wallet_session) = pair(&test, &sepolia, NoopSessionHandler).await?;
+    let pairing = test
+        .dapp
+        .pairing()
+        .ok_or_else(|| format_err!("no pairing!"))?;
+    test.wallet_manager.delete().await?;
+    let (deleted_topic, request) = timeout(Duration::from_secs(5), pair_deleted.recv())
+        .await?
+        .ok_or_else(|| format_err!("pair delete handler dropped"))?;
+    assert_eq!(pairing.topic, deleted_topic);
+    assert_eq!(PairDeleteRequest::default(), request);
+    assert!(test.dapp.pairing().is_none());
+    for session in [&dapp_session, &wallet_session] {
+        assert!(session.is_closed());
+        assert_matches!(
+            timeout(Duration::from_millis(100), session.ping()).await?,
+            Err(monedero_mesh::Error::NoClientSession(_))
+        );
+    }
+    for actors in [&test.dapp_actors, &test.wallet_actors] {
+        assert_eq!(0, actors.session().send(RegisteredComponents).await?);
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_shutdown() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let (session, _) = pair(&test, &all_chains(), NoopSessionHandler).await?;
+    let topic = session.topic();
+    let mut wallet_events = test.wallet_manager.lifecycle();
+
+    timeout(
+        Duration::from_secs(10),
+        test.dapp_manager.shutdown_with(ShutdownOptions {
+            delete_sessions: true,
+            ..Default::default()
+        }),
+    )
+    .await??;
+    assert!(test.dapp_manager.is_shut_down());
+    assert!(session.is_closed());
+    assert_eq!(
+        SessionLifecycleEvent::DeletedByPeer { topic },
+        next_event(&mut wallet_events).await?
+    );
+    assert!(test
+        .dapp_actors
+        .session()
+        .send(RegisteredComponents)
+        .await
+        .is_err());
+    assert_matches!(
+        test.dapp_manager.open_socket().await,
+        Err(monedero_mesh::Error::ShutDown)
+    );
+    // the pairing is kept for the next manager
+    assert!(test.dapp.pairing().is_some());
+    Ok(())
+}
+
+/// Holds every request until a permit is added.
+struct GatedHandler(Arc<Semaphore>);
+
+impl SessionEventHandler for GatedHandler {}
+
+#[async_trait]
+impl SessionHandler for GatedHandler {
+    async fn request(&self, _request: SessionRequestRequest) -> WalletRequestResponse {
+        let _permit = self.0.acquire().await;
+        WalletRequestResponse::Success(json!(true))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_busy_when_queues_are_full() -> anyhow::Result<()> {
+    let test = init_test_components_with(|b| {
+        b.queue_limits(QueueLimits {
+            mailbox: Some(1),
+            inbound_requests: 1,
+            inbound_responses: 16,
+            ..QueueLimits::default()
+        })
+    })
+    .await?;
+    let gate = Arc::new(Semaphore::new(0));
+    let sepolia = [ChainId::EIP155(AlloyChain::sepolia())];
+    let (session, _) = pair(&test, &sepolia, GatedHandler(gate.clone())).await?;
+
+    let request = RequestParams::SessionRequest(SessionRequestRequest {
+        request: RequestMethod {
+            method: monedero_domain::namespaces::Method::Other(String::from("busy_test")),
+            params: json!([]),
+            expiry: None,
+        },
+        chain_id: ChainId::EIP155(AlloyChain::sepolia()),
+    });
+    let mut requests = Vec::new();
+    for _ in 0..10 {
+        let session = session.clone();
+        let request = request.clone();
+        requests.push(tokio::spawn(async move {
+            session.publish_request::<serde_json::Value>(request).await
+        }));
+    }
+    for _ in 0..50 {
+        if test.wallet_manager.queue_depth().shed > 0 {
+            break;
+        }
+        yield_ms(100).await;
+    }
+    let depth = test.wallet_manager.queue_depth();
+    assert!(depth.shed > 0, "{depth:?}");
+    assert!(depth.inbound_requests <= 1, "{depth:?}");
+
+    gate.add_permits(10);
+    let (mut answered, mut busy) = (0, 0);
+    for request in requests {
+        match timeout(Duration::from_secs(5), request).await?? {
+            Ok(_) => answered += 1,
+            Err(monedero_mesh::Error::RpcError(e)) if e.overload() == Some(OverloadErrors::Busy) => {
+                busy += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    assert_eq!(10, answered + busy);
+    let depth = test.wallet_manager.queue_depth();
+    assert_eq!(depth.shed, busy);
+    assert_eq!(0, depth.inbound_requests);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_shutdown_answers_queued_requests() -> anyhow::Result<()> {
+    let test = init_test_components_with(|b| {
+        b.queue_limits(QueueLimits {
+            mailbox: Some(1),
+            inbound_requests: 8,
+            inbound_responses: 16,
+            ..QueueLimits::default()
+        })
+    })
+    .await?;
+    let gate = Arc::new(Semaphore::new(0));
+    let sepolia = [ChainId::EIP155(AlloyChain::sepolia())];
+    let (session, _) = pair(&test, &sepolia, GatedHandler(gate.clone())).await?;
+
+    let request = RequestParams::SessionRequest(SessionRequestRequest {
+        request: RequestMethod {
+            method: monedero_domain::namespaces::Method::Other(String::from("queued_test")),
+            params: json!([]),
+            expiry: None,
+        },
+        chain_id: ChainId::EIP155(AlloyChain::sepolia()),
+    });
+    let mut requests = Vec::new();
+    for _ in 0..4 {
+        let session = session.clone();
+        let request = request.clone();
+        requests.push(tokio::spawn(async move {
+            session.publish_request::<serde_json::Value>(request).await
+        }));
+    }
+    for _ in 0..50 {
+        if test.wallet_manager.queue_depth().inbound_requests > 0 {
+            break;
+        }
+        yield_ms(100).await;
+    }
+    assert!(test.wallet_manager.queue_depth().inbound_requests > 0);
+
+    let wallet_manager = test.wallet_manager.clone();
+    let shutdown = tokio::spawn(async move { wallet_manager.shutdown().await });
+    yield_ms(200).await;
+    gate.add_permits(4);
+    for request in requests {
+        assert_eq!(json!(true), timeout(Duration::from_secs(5), request).await???);
+    }
+    timeout(Duration::from_secs(10), shutdown).await???;
+    assert_eq!(0, test.wallet_manager.queue_depth().inbound_requests);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_rate_limited_peer() -> anyhow::Result<()> {
+    let t = init_test_components_with(|b| {
+        b.rate_limits(RateLimits::default().with_method(
+            "wc_sessionPing",
+            Some(RateLimit::new(2, Duration::from_secs(30))),
+        ))
+    })
+    .await?;
+    let mut wallet_events = t.wallet_manager.lifecycle();
+    let (session, _) = pair(&t, &all_chains(), NoopSessionHandler).await?;
+
+    assert!(session.ping().await?);
+    assert!(session.ping().await?);
+    assert_matches!(
+        session.ping().await,
+        Err(monedero_mesh::Error::RpcError(e)) if e.overload() == Some(OverloadErrors::RateLimited)
+    );
+    loop {
+        if let SessionLifecycleEvent::RateLimited { topic, method } =
+            next_event(&mut wallet_events).await?
+        {
+            assert_eq!(session.topic(), topic);
+            assert_eq!("wc_sessionPing", method);
+            break;
+        }
+    }
+    // other methods are counted apart
+    assert!(session.delete().await);
+    Ok(())
+}
+
+/// Answers every request with its name.
+struct Approver(&'static str);
+
+impl SessionEventHandler for Approver {}
+
+#[async_trait]
+impl SessionHandler for Approver {
+    async fn request(&self, _request: SessionRequestRequest) -> WalletRequestResponse {
+        WalletRequestResponse::Success(json!(self.0))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_wallet_policy() -> anyhow::Result<()> {
+    let t = init_test_components().await?;
+    let policy = Policy::from_toml(
+        r#"
+        [[rules]]
+        name = "blocked dapp"
+        urls = ["https://evil.example"]
+        outcome = "reject"
+
+        [[rules]]
+        name = "devnet signatures"
+        chains = ["eip155:11155111"]
+        methods = ["personal_sign"]
+        params = [{ pointer = "/1", equals = "0xabc" }]
+        outcome = { approve = "signer" }
+
+        [[rules]]
+        methods = ["eth_sendTransaction"]
+        outcome = "reject"
+        "#,
+    )?;
+    assert!(t
+        .wallet
+        .set_policy(PolicyEngine::new(policy.clone()))
+        .is_err());
+    t.wallet
+        .set_policy(PolicyEngine::new(policy).approver("signer", Approver("signer")))?;
+    let (session, _) = pair(&t, &all_chains(), NoopSessionHandler).await?;
+
+    let request = |method: &str, params| {
+        RequestParams::SessionRequest(SessionRequestRequest {
+            request: RequestMethod {
+                method: method.parse().expect("method"),
+                params,
+                expiry: None,
+            },
+            chain_id: ChainId::EIP155(AlloyChain::sepolia()),
+        })
+    };
+    let approved: serde_json::Value = session
+        .publish_request(request("personal_sign", json!(["hi", "0xabc"])))
+        .await?;
+    assert_eq!(json!("signer"), approved);
+    // falls back to the session handler
+    let asked: serde_json::Value = session
+        .publish_request(request("personal_sign", json!(["hi", "0xdef"])))
+        .await?;
+    assert_eq!(json!({}), asked);
+    assert_matches!(
+        session
+            .publish_request::<serde_json::Value>(request("eth_sendTransaction", json!([])))
+            .await,
+        Err(monedero_mesh::Error::RpcError(e)) if e.sdk() == Some(SdkErrors::UserRejected)
+    );
+    Ok(())
+}
+
+/// Redacts outbound session request params, refuses `blocked` requests
+/// before they leave and session pings when they arrive.
+#[derive(Clone, Default)]
+struct TestMiddleware {
+    responses: Arc<AtomicUsize>,
+}
+
+fn stopped() -> ErrorParams {
+    ErrorParams {
+        code: Some(4242),
+        message: String::from("stopped by middleware"),
+    }
+}
+
+#[async_trait]
+impl Middleware for TestMiddleware {
+    async fn outbound_request(&self, _topic: &Topic, params: RequestParams) -> Flow<RequestParams> {
+        match params {
+            RequestParams::SessionRequest(r) if r.request.method.to_string() == "blocked" => {
+                Flow::Stop(stopped())
+            }
+            RequestParams::SessionRequest(mut r) => {
+                r.request.params = json!("redacted");
+                Flow::Next(RequestParams::SessionRequest(r))
+            }
+            params => Flow::Next(params),
+        }
+    }
+
+    async fn inbound_request(&self, request: RpcRequest) -> Flow<RpcRequest> {
+        match request.payload.params {
+            RequestParams::SessionPing(()) => Flow::Stop(stopped()),
+            _ => Flow::Next(request),
+        }
+    }
+
+    async fn inbound_response(&self, response: Response) -> Flow<Response> {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        Flow::Next(response)
+    }
+}
+
+/// Answers with the params of the request.
+struct Echo;
+
+impl SessionEventHandler for Echo {}
+
+#[async_trait]
+impl SessionHandler for Echo {
+    async fn request(&self, request: SessionRequestRequest) -> WalletRequestResponse {
+        WalletRequestResponse::Success(request.request.params)
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_middleware() -> anyhow::Result<()> {
+    let middleware = TestMiddleware::default();
+    let responses = middleware.responses.clone();
+    let test = init_test_components_with(move |b| b.middleware(middleware.clone())).await?;
+    let sepolia = [ChainId::EIP155(AlloyChain::sepolia())];
+    let (session, _) = pair(&test, &sepolia, Echo).await?;
+    let seen = responses.load(Ordering::Relaxed);
+    assert!(seen > 0);
+
+    let request = |method: &str| {
+        RequestParams::SessionRequest(SessionRequestRequest {
+            request: RequestMethod {
+                method: method.parse().expect("method"),
+                params: json!(["secret"]),
+                expiry: None,
+            },
+            chain_id: ChainId::EIP155(AlloyChain::sepolia()),
+        })
+    };
+    let echoed: serde_json::Value = session.publish_request(request("personal_sign")).await?;
+    assert_eq!(json!("redacted"), echoed);
+    assert!(responses.load(Ordering::Relaxed) > seen);
+    assert_matches!(
+        session.publish_request::<serde_json::Value>(request("blocked")).await,
+        Err(monedero_mesh::Error::RpcError(e)) if e.code == Some(ErrorCode::Unknown(4242))
+    );
+    assert_matches!(
+        session.ping().await,
+        Err(monedero_mesh::Error::RpcError(e)) if e.code == Some(ErrorCode::Unknown(4242))
+    );
+    Ok(())
+}
+
+struct PortfolioSync;
+
+impl CustomMethod for PortfolioSync {
+    type Params = u64;
+    type Result = Vec<String>;
+
+    const METHOD: &'static str = "portfolio_sync";
+    const REQUEST: IrnMetadata = IrnMetadata {
+        tag: 4000,
+        ttl: 300,
+        prompt: false,
+    };
+    const RESPONSE: IrnMetadata = IrnMetadata {
+        tag: 4001,
+        ttl: 300,
+        prompt: false,
+    };
+}
+
+struct Unregistered;
+
+impl CustomMethod for Unregistered {
+    type Params = ();
+    type Result = ();
+
+    const METHOD: &'static str = "portfolio_unknown";
+    const REQUEST: IrnMetadata = IrnMetadata {
+        tag: 4002,
+        ttl: 300,
+        prompt: false,
+    };
+    const RESPONSE: IrnMetadata = IrnMetadata {
+        tag: 4003,
+        ttl: 300,
+        prompt: false,
+    };
+}
+
+struct Portfolio;
+
+#[async_trait]
+impl CustomHandler<PortfolioSync> for Portfolio {
+    async fn handle(&self, cursor: u64) -> Result<Vec<String>, ErrorParams> {
+        if cursor > 1 {
+            return Err(ErrorParams {
+                code: Some(4100),
+                message: String::from("cursor out of range"),
+            });
+        }
+        Ok(vec![format!("asset-{cursor}")])
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_custom_method() -> anyhow::Result<()> {
+    let test = init_test_components_with(ReownBuilder::custom_method::<PortfolioSync>).await?;
+    let sepolia = [ChainId::EIP155(AlloyChain::sepolia())];
+    let (session, wallet_session) = pair(&test, &sepolia, NoopSessionHandler).await?;
+
+    // no handler yet
+    assert_matches!(
+        session.request::<PortfolioSync>(0).await,
+        Err(monedero_mesh::Error::RpcError(e)) if e.sdk() == Some(SdkErrors::UnsupportedMethods)
+    );
+
+    wallet_session.on_request(Portfolio);
+    assert_eq!(
+        vec![String::from("asset-1")],
+        session.request::<PortfolioSync>(1).await?
+    );
+    assert_matches!(
+        session.request::<PortfolioSync>(2).await,
+        Err(monedero_mesh::Error::RpcError(e)) if e.code == Some(ErrorCode::Unknown(4100)) && e.message == "cursor out of range"
+    );
+    assert_matches!(
+        session.request::<Unregistered>(()).await,
+        Err(monedero_mesh::Error::InvalidCustomMethod(_))
+    );
+    // Sign API methods keep working next to custom ones
+    assert!(session.ping().await?);
+    Ok(())
+}
+
+/// Checks the params the client sent, then signs with a fixed signature and
+/// sends with a fixed hash, Solana transactions come back unchanged.
+struct Signer;
+
+/// `result` if the wallet received `expected` params, otherwise an error so
+/// the request fails in the test instead of the wallet actor panicking.
+fn expect_params(
+    request: &SessionRequestRequest,
+    expected: &Value,
+    result: Value,
+) -> WalletRequestResponse {
+    if &request.request.params == expected {
+        return WalletRequestResponse::Success(result);
+    }
+    error!(
+        "{} expected params {expected} got {}",
+        request.request.method, request.request.params
+    );
+    WalletRequestResponse::Error(SdkErrors::InvalidMethod)
+}
+
+impl SessionEventHandler for Signer {}
+
+#[async_trait]
+impl SessionHandler for Signer {
+    async fn request(&self, request: SessionRequestRequest) -> WalletRequestResponse {
+        match request.request.method.to_string().as_str() {
+            "eth_chainId" => expect_params(&request, &json!([]), json!("0xdeadbeef")),
+            "wallet_switchEthereumChain" => {
+                expect_params(&request, &json!([{ "chainId": "0x4268" }]), Value::Null)
+            }
+            "wallet_sendCalls" => expect_params(
+                &request,
+                &json!([{
+                    "version": "2.0.0",
+                    "from": Address::ZERO,
+                    "calls": [{ "to": Address::ZERO }],
+                    "atomicRequired": false,
+                    "chainId": "0xaa36a7",
+                }]),
+                json!({ "id": "0xdeadbeef" }),
+            ),
+            "wallet_getCallsStatus" => expect_params(
+                &request,
+                &json!(["0xdeadbeef"]),
+                json!({
+                    "version": "2.0.0",
+                    "id": "0xdeadbeef",
+                    "chainId": "0xaa36a7",
+                    "status": 200,
+                    "atomic": true,
+                    "receipts": [],
+                }),
+            ),
+            "personal_sign" => expect_params(
+                &request,
+                &json!(["0x68656c6c6f", Address::ZERO]),
+                json!("0xdeadbeef"),
+            ),
+            "eth_sendTransaction" => expect_params(
+                &request,
+                &json!([{ "from": Address::ZERO, "to": Address::ZERO }]),
+                json!(B256::repeat_byte(0xab)),
+            ),
+            "solana_signMessage" => expect_params(
+                &request,
+                &json!({ "message": Encoding::Base58.encode(b"hello"), "pubkey": "pubkey" }),
+                json!({ "signature": Signature([1; 64]) }),
+            ),
+            "solana_getAccounts" => {
+                expect_params(&request, &json!({}), json!([{ "pubkey": "pubkey" }]))
+            }
+            "solana_signAndSendTransaction" => expect_params(
+                &request,
+                &json!({
+                    "transaction": Encoding::Base64.encode(&[1, 2, 3]),
+                    "sendOptions": { "skipPreflight": true },
+                }),
+                json!({ "signature": Signature([3; 64]) }),
+            ),
+            "solana_signAllTransactions" => WalletRequestResponse::Success(json!({
+                "transactions": request.request.params["transactions"],
+            })),
+            "solana_signTransaction" => WalletRequestResponse::Success(json!({
+                "signature": Signature([2; 64]),
+                "transaction": request.request.params["transaction"],
+            })),
+            _ => WalletRequestResponse::Error(SdkErrors::InvalidMethod),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_eip155_client() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let namespaces = Namespaces::from(&[ChainId::EIP155(AlloyChain::sepolia())])
+        .with_methods(EipMethod::eip5792());
+    let (session, _) = pair(&test, namespaces, Signer).await?;
+
+    let sepolia = session.eip155(AlloyChain::sepolia());
+    assert_eq!(0xdead_beef, sepolia.chain().await?);
+    sepolia.switch_chain(AlloyChain::holesky()).await?;
+    let calls = SendCalls::new(Address::ZERO, vec![Call {
+        to: Address::ZERO,
+        ..Default::default()
+    }]);
+    let id = sepolia.send_calls(&calls).await?;
+    assert_eq!("0xdeadbeef", id);
+    let status = sepolia.calls_status(&id).await?;
+    assert_eq!(CallsStatus::Confirmed, status.status);
+    assert_eq!(Some(true), status.atomic);
+    assert_eq!(
+        Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]),
+        sepolia.personal_sign("hello", Address::ZERO).await?
+    );
+    let tx = TransactionRequest {
+        from: Address::ZERO,
+        to: Some(Address::ZERO),
+        ..Default::default()
+    };
+    assert_eq!(B256::repeat_byte(0xab), sepolia.send_transaction(&tx).await?);
+    assert_matches!(
+        session
+            .eip155(AlloyChain::mainnet())
+            .personal_sign("hello", Address::ZERO)
+            .await,
+        Err(monedero_mesh::Error::MethodNotSettled(_, _))
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_eip155_client_after_update() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let (session, wallet_session) = pair(&test, &all_chains(), Signer).await?;
+    let not_settled = |chain: AlloyChain| {
+        let session = session.clone();
+        async move {
+            matches!(
+                session.eip155(chain).chain().await,
+                Err(monedero_mesh::Error::MethodNotSettled(_, _))
+            )
+        }
+    };
+    assert!(not_settled(AlloyChain::mainnet()).await);
+
+    let update = |namespaces: Namespaces| {
+        RequestParams::SessionUpdate(SessionUpdateRequest { namespaces })
+    };
+
+    // the wallet adds mainnet
+    let mut namespaces = session.namespaces();
+    let eip155 = namespaces
+        .get_mut(&NamespaceName::EIP155)
+        .ok_or_else(|| format_err!("no eip155 namespace"))?;
+    eip155.chains.0.insert(ChainId::EIP155(AlloyChain::mainnet()));
+    assert!(wallet_session.publish_request::<bool>(update(namespaces.clone())).await?);
+    assert_eq!(0xdead_beef, session.eip155(AlloyChain::mainnet()).chain().await?);
+
+    // then drops eth_chainId, which the dapp did not require
+    let eip155 = namespaces
+        .get_mut(&NamespaceName::EIP155)
+        .ok_or_else(|| format_err!("no eip155 namespace"))?;
+    eip155.methods.0.remove(&Method::EIP155(EipMethod::ChainId));
+    assert!(wallet_session.publish_request::<bool>(update(namespaces)).await?);
+    assert!(not_settled(AlloyChain::sepolia()).await);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_solana_client() -> anyhow::Result<()> {
+    let test = init_test_components().await?;
+    let namespaces = Namespaces::from(&[ChainId::Solana(ChainType::Dev)]).with_methods([
+        Method::Solana(SolanaMethod::SignAllTransactions),
+        Method::Solana(SolanaMethod::SignAndSendTransaction),
+    ]);
+    let (session, _) = pair(&test, namespaces, Signer).await?;
+
+    let devnet = session.solana(ChainType::Dev);
+    assert_eq!(vec![String::from("pubkey")], devnet.accounts().await?);
+    let transactions = [vec![1, 2, 3], vec![4, 5]];
+    assert_eq!(
+        transactions.to_vec(),
+        devnet.sign_all_transactions(&transactions).await?
+    );
+    assert_eq!(
+        Signature([1; 64]),
+        devnet.sign_message(b"hello", "pubkey").await?
+    );
+    let signed = devnet.sign_transaction(&[1, 2, 3]).await?;
+    assert_eq!(Signature([2; 64]), signed.signature);
+    assert_eq!(Some(vec![1, 2, 3]), signed.transaction);
+    let signed = devnet
+        .encoding(Encoding::Base58)
+        .sign_transaction(&[4, 5])
+        .await?;
+    assert_eq!(Some(vec![4, 5]), signed.transaction);
+    let options = SendOptions {
+        skip_preflight: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(
+        Signature([3; 64]),
+        session
+            .solana(ChainType::Dev)
+            .sign_and_send_transaction(&[1, 2, 3], Some(options))
+            .await?
+    );
+    assert_matches!(
+        session
+            .solana(ChainType::Main)
+            .sign_message(b"hello", "pubkey")
+            .await,
+        Err(monedero_mesh::Error::MethodNotSettled(_, _))
+    );
+    Ok(())
+}
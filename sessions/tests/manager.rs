@@ -1,7 +1,22 @@
 use {
     async_trait::async_trait,
-    monedero_mesh::{SocketEvent, SocketListener},
-    std::sync::{Arc, Mutex},
+    monedero_domain::{ProjectId, Topic},
+    monedero_mesh::{
+        auth_token,
+        KvStorage,
+        MockRelay,
+        ReconnectPolicy,
+        ReownBuilder,
+        SocketEvent,
+        SocketListener,
+        AUTH_URL,
+        CLOSE_UNAUTHORIZED,
+    },
+    monedero_relay::LoopbackRelay,
+    std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
 };
 mod test_utils;
 use test_utils::*;
@@ -19,6 +34,17 @@ impl SocketListener for DummySocketListener {
     }
 }
 
+/// Polls `f` for up to 5 seconds.
+async fn eventually(f: impl Fn() -> bool) -> bool {
+    for _ in 0..50 {
+        if f() {
+            return true;
+        }
+        yield_ms(100).await;
+    }
+    f()
+}
+
 //#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
 #[allow(dead_code)]
 async fn test_relay_pair_ping() -> anyhow::Result<()> {
@@ -77,3 +103,257 @@ async fn test_relay_pair_ping() -> anyhow::Result<()> {
 //    assert_eq!(2, l.len());
 //    Ok(())
 //}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_persistent_client_identity() -> anyhow::Result<()> {
+    let p = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+    let relay = LoopbackRelay::new();
+    let store = KvStorage::mem();
+    let build = |store: KvStorage| {
+        let builder = ReownBuilder::new(p.clone())
+            .connect_opts(relay.connection_opts(&p).without_auth())
+            .store(store);
+        async move { builder.build().await }
+    };
+    let first = build(store.clone()).await?;
+    let client_id = first.client_id().expect("identity from store");
+    first.shutdown().await?;
+
+    let restarted = build(store).await?;
+    let token = relay.auth_tokens().pop();
+    assert_eq!(Some(client_id.clone()), restarted.client_id());
+    let other = build(KvStorage::mem()).await?;
+    assert_ne!(Some(client_id), other.client_id());
+
+    // a refresh reconnects with a new token, tokens minted within the same
+    // second are identical
+    yield_ms(1100).await;
+    let connections = relay.auth_tokens().len();
+    restarted.refresh_auth().await?;
+    let tokens = relay.auth_tokens();
+    assert_eq!(connections + 1, tokens.len());
+    assert_ne!(token, tokens.last().cloned());
+    restarted.subscribe(Topic::generate()).await?;
+
+    let fixed = ReownBuilder::new(p.clone())
+        .connect_opts(relay.connection_opts(&p).without_auth())
+        .auth(auth_token(AUTH_URL))
+        .store(KvStorage::mem())
+        .build()
+        .await?;
+    assert!(fixed.client_id().is_none());
+
+    // a token in the connection options is kept
+    let token = auth_token(AUTH_URL);
+    let mut opts = relay.connection_opts(&p);
+    opts.set_auth(token.clone());
+    let caller = ReownBuilder::new(p.clone())
+        .connect_opts(opts.clone())
+        .store(KvStorage::mem())
+        .build()
+        .await?;
+    assert!(caller.client_id().is_none());
+    assert_eq!(Some(token.to_string()), relay.auth_tokens().pop());
+    let conflict = ReownBuilder::new(p.clone())
+        .connect_opts(opts)
+        .auth(auth_token(AUTH_URL))
+        .store(KvStorage::mem())
+        .build()
+        .await;
+    assert!(matches!(conflict, Err(monedero_mesh::Error::AuthConflict)));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_relay_failover() -> anyhow::Result<()> {
+    let p = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+    let primary = MockRelay::builder().ephemeral().build().await?;
+    let backup = MockRelay::builder().ephemeral().build().await?;
+    let primary_address = format!("ws://{}", primary.address());
+    let backup_address = format!("ws://{}", backup.address());
+
+    // an unreachable first choice is skipped
+    let mgr = ReownBuilder::new(p.clone())
+        .relays(["ws://127.0.0.1:1", &primary_address, &backup_address])
+        .relay_retry_after(Duration::from_millis(500))
+        .store(KvStorage::mem())
+        .build()
+        .await?;
+    assert_eq!(Some(primary_address.clone()), mgr.active_relay());
+    assert!(eventually(|| primary.client_ids().len() == 1).await);
+    // each relay gets a token minted for its own address
+    let audience = |relay: &MockRelay| relay.state().clients.first()?.audience.clone();
+    assert_eq!(Some(primary_address.clone()), audience(&primary));
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    mgr.register_socket_listener(DummySocketListener {
+        events: events.clone(),
+    })
+    .await;
+    primary.shutdown().await;
+    assert!(eventually(|| mgr.active_relay().as_ref() == Some(&backup_address)).await);
+    assert!(eventually(|| backup.client_ids().len() == 1).await);
+    assert_eq!(Some(backup_address.clone()), audience(&backup));
+    mgr.subscribe(Topic::generate()).await?;
+    // listeners are notified from another task
+    assert!(
+        eventually(|| events
+            .lock()
+            .unwrap()
+            .contains(&SocketEvent::ActiveRelay(backup_address.clone())))
+        .await
+    );
+    assert!(events
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|e| matches!(e, SocketEvent::ForceDisconnect(_))));
+
+    // a recovered primary is picked again on a reconnect after retry_after
+    let primary = MockRelay::builder()
+        .address(primary.address())
+        .build()
+        .await?;
+    yield_ms(500).await;
+    mgr.reconnect().await?;
+    assert_eq!(Some(primary_address), mgr.active_relay());
+    assert!(eventually(|| primary.client_ids().len() == 1).await);
+    mgr.subscribe(Topic::generate()).await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_reconnect_gives_up() -> anyhow::Result<()> {
+    let p = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+    let relay = MockRelay::builder().ephemeral().build().await?;
+    let address = relay.address();
+    let mgr = ReownBuilder::new(p.clone())
+        .connect_opts(relay.connection_opts(&p))
+        .reconnect_policy(ReconnectPolicy {
+            initial_delay: Duration::from_millis(10),
+            initial_interval: Duration::from_millis(50),
+            max_interval: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_elapsed_time: Some(Duration::from_millis(300)),
+        })
+        .store(KvStorage::mem())
+        .build()
+        .await?;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    mgr.register_socket_listener(DummySocketListener {
+        events: events.clone(),
+    })
+    .await;
+
+    relay.shutdown().await;
+    for _ in 0..50 {
+        if events
+            .lock()
+            .unwrap()
+            .contains(&SocketEvent::ReconnectGaveUp)
+        {
+            break;
+        }
+        yield_ms(100).await;
+    }
+    let seen = events.lock().unwrap().clone();
+    assert!(seen.contains(&SocketEvent::Reconnecting(1)));
+    assert!(seen.contains(&SocketEvent::Reconnecting(2)));
+    assert_eq!(Some(&SocketEvent::ReconnectGaveUp), seen.last());
+
+    let relay = MockRelay::builder().address(address).build().await?;
+    mgr.reconnect().await?;
+    assert!(eventually(|| relay.client_ids().len() == 1).await);
+    mgr.subscribe(Topic::generate()).await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 10)]
+async fn test_close_reason() -> anyhow::Result<()> {
+    let p = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
+    let relay = MockRelay::builder().ephemeral().build().await?;
+    let policy = ReconnectPolicy {
+        initial_delay: Duration::from_millis(10),
+        ..ReconnectPolicy::default()
+    };
+    let connect = |relay: &MockRelay, auth: Option<_>| {
+        let mut builder = ReownBuilder::new(p.clone())
+            .connect_opts(relay.connection_opts(&p).without_auth())
+            .reconnect_policy(policy.clone())
+            .store(KvStorage::mem());
+        if let Some(auth) = auth {
+            builder = builder.auth(auth);
+        }
+        async move {
+            let mgr = builder.build().await?;
+            let events = Arc::new(Mutex::new(Vec::new()));
+            mgr.register_socket_listener(DummySocketListener {
+                events: events.clone(),
+            })
+            .await;
+            anyhow::Ok((mgr, events))
+        }
+    };
+    let reason_of = |events: &Arc<Mutex<Vec<SocketEvent>>>| {
+        events.lock().unwrap().iter().find_map(|e| match e {
+            SocketEvent::ForceDisconnect(reason) => Some(reason.clone()),
+            _ => None,
+        })
+    };
+
+    // a normal close is reported and reconnects
+    let (_mgr, events) = connect(&relay, None).await?;
+    assert!(eventually(|| relay.client_ids().len() == 1).await);
+    relay
+        .close_with(relay.client_ids()[0], 1001, "going away")
+        .await;
+    assert!(
+        eventually(|| events
+            .lock()
+            .unwrap()
+            .contains(&SocketEvent::Reconnecting(1)))
+        .await
+    );
+    let reason = reason_of(&events).expect("disconnect event");
+    assert_eq!(Some(1001), reason.code);
+    assert_eq!(Some("going away"), reason.reason.as_deref());
+    assert!(!reason.is_auth_failure());
+
+    // a rejected minted token is renewed once
+    let id = relay.client_ids()[0];
+    events.lock().unwrap().clear();
+    relay
+        .close_with(id, CLOSE_UNAUTHORIZED, "invalid jwt")
+        .await;
+    assert!(
+        eventually(|| events
+            .lock()
+            .unwrap()
+            .contains(&SocketEvent::Reconnecting(1)))
+        .await
+    );
+    assert!(reason_of(&events)
+        .expect("disconnect event")
+        .is_auth_failure());
+
+    // a rejected fixed token is not retried
+    let relay = MockRelay::builder().ephemeral().build().await?;
+    let (_mgr, events) = connect(&relay, Some(auth_token(AUTH_URL))).await?;
+    assert!(eventually(|| relay.client_ids().len() == 1).await);
+    relay
+        .close_with(relay.client_ids()[0], CLOSE_UNAUTHORIZED, "invalid jwt")
+        .await;
+    assert!(
+        eventually(|| events
+            .lock()
+            .unwrap()
+            .contains(&SocketEvent::ReconnectGaveUp))
+        .await
+    );
+    assert!(!events
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|e| matches!(e, SocketEvent::Reconnecting(_))));
+    Ok(())
+}
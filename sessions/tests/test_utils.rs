@@ -19,7 +19,6 @@ use {
     },
     monedero_mesh::{
         init_tracing,
-        mock_connection_opts,
         rpc::{
             Metadata,
             RelayProtocol,
@@ -32,13 +31,14 @@ use {
         Actors,
         Dapp,
         KvStorage,
-        MockRelay,
+        PairingManager,
         ReownBuilder,
         Result,
         SdkErrors,
         Wallet,
         WalletSettlementHandler,
     },
+    monedero_relay::LoopbackRelay,
     std::{
         collections::{BTreeMap, BTreeSet},
         time::Duration,
@@ -54,7 +54,9 @@ pub struct TestStuff {
     pub(crate) wallet_actors: Actors,
     pub(crate) dapp: Dapp,
     pub(crate) wallet: Wallet,
-    pub relay: MockRelay,
+    pub(crate) dapp_manager: PairingManager,
+    pub(crate) wallet_manager: PairingManager,
+    pub relay: LoopbackRelay,
 }
 
 pub async fn yield_ms(ms: u64) {
@@ -80,7 +82,7 @@ impl WalletSettlementHandler for WalletProposal {
                 .collect();
 
             let methods = match name {
-                NamespaceName::EIP155 => EipMethod::defaults(),
+                NamespaceName::EIP155 => EipMethod::all(),
                 NamespaceName::Solana => SolanaMethod::defaults(),
                 NamespaceName::Other(_) => BTreeSet::from([Method::Other("unknown".to_owned())]),
             };
@@ -121,35 +123,48 @@ impl WalletSettlementHandler for WalletProposal {
 }
 
 pub async fn init_test_components() -> anyhow::Result<TestStuff> {
+    init_test_components_with(|b| b).await
+}
+
+/// `configure` is applied to the builders of both managers.
+pub async fn init_test_components_with(
+    configure: impl Fn(ReownBuilder) -> ReownBuilder,
+) -> anyhow::Result<TestStuff> {
     init_tracing();
     let p = ProjectId::from("987f2292c12194ae69ddb6c52ceb1d62");
-    let dapp_opts = mock_connection_opts(&p);
-    let wallet_opts = mock_connection_opts(&p);
-    let relay = monedero_mesh::MockRelay::start().await?;
-    let dapp_manager = ReownBuilder::new(p.clone())
-        .connect_opts(dapp_opts)
-        .store(KvStorage::mem())
-        .build()
-        .await?;
-    let wallet_manager = ReownBuilder::new(p)
-        .connect_opts(wallet_opts)
-        .store(KvStorage::mem())
-        .build()
-        .await?;
+    let relay = LoopbackRelay::new();
+    let dapp_opts = relay.connection_opts(&p);
+    let wallet_opts = relay.connection_opts(&p);
+    let dapp_manager = configure(
+        ReownBuilder::new(p.clone())
+            .connect_opts(dapp_opts)
+            .store(KvStorage::mem()),
+    )
+    .build()
+    .await?;
+    let wallet_manager = configure(
+        ReownBuilder::new(p)
+            .connect_opts(wallet_opts)
+            .store(KvStorage::mem()),
+    )
+    .build()
+    .await?;
     let dapp_actors = dapp_manager.actors();
     let wallet_actors = wallet_manager.actors();
     let md = Metadata {
         name: "mock-dapp".to_string(),
         ..Default::default()
     };
-    let dapp = Dapp::new(dapp_manager, md).await?;
-    let wallet = Wallet::new(wallet_manager, WalletProposal {}).await?;
+    let dapp = Dapp::new(dapp_manager.clone(), md).await?;
+    let wallet = Wallet::new(wallet_manager.clone(), WalletProposal {}).await?;
     yield_ms(500).await;
     let t = TestStuff {
         dapp_actors: dapp_actors.clone(),
         wallet_actors: wallet_actors.clone(),
         dapp,
         wallet,
+        dapp_manager,
+        wallet_manager,
         relay,
     };
     Ok(t)
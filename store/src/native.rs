@@ -4,8 +4,9 @@ use {
     serde::{Deserialize, Serialize},
     sha2::Digest,
     std::{
+        collections::BTreeSet,
         path::{Path, PathBuf},
-        sync::Arc,
+        sync::{Arc, RwLock},
     },
     tracing::{debug, info},
     url::Url,
@@ -14,6 +15,8 @@ use {
 #[derive(Clone)]
 pub struct KvStorage {
     store: Arc<KeyValueStore>,
+    /// Keys that survive [`KvStorage::clear`]
+    retained: Arc<RwLock<BTreeSet<String>>>,
 }
 
 impl Default for KvStorage {
@@ -32,6 +35,7 @@ impl KvStorage {
         )?;
         Ok(Self {
             store: Arc::new(store),
+            retained: Arc::default(),
         })
     }
 
@@ -73,6 +77,7 @@ impl KvStorage {
         let store = KeyValueStore::new(&Url::parse("memory://").unwrap(), namespace).unwrap();
         Self {
             store: Arc::new(store),
+            retained: Arc::default(),
         }
     }
 
@@ -81,6 +86,14 @@ impl KvStorage {
             Segment::parse(key.as_ref()).map_err(|_| SegmentErr(String::from(key.as_ref())))?;
         Ok(Key::new_global(seg))
     }
+
+    /// Keep `key` when the storage is cleared, e.g. for long lived identities
+    /// that must outlive the pairing state.
+    pub fn retain(&self, key: impl Into<String>) {
+        if let Ok(mut retained) = self.retained.write() {
+            retained.insert(key.into());
+        }
+    }
 }
 
 impl KvStorage {
@@ -121,9 +134,25 @@ impl KvStorage {
 
     #[tracing::instrument(level = "debug", skip(self))]
     pub fn clear(&self) {
+        let kept: Vec<(Key, serde_json::Value)> = self
+            .retained
+            .read()
+            .map(|retained| {
+                retained
+                    .iter()
+                    .filter_map(|k| Self::key_segment(k).ok())
+                    .filter_map(|k| self.store.get(&k).ok().flatten().map(|v| (k, v)))
+                    .collect()
+            })
+            .unwrap_or_default();
         if let Err(e) = self.store.clear() {
             debug!("failed to clear storage {e}");
         }
+        for (k, v) in kept {
+            if let Err(e) = self.store.store(&k, v) {
+                debug!("failed to restore retained key {k} {e}");
+            }
+        }
     }
 }
 
@@ -144,8 +173,11 @@ mod tests {
         assert!(store.get::<String>("mine")?.is_none());
 
         store.set("mine", Topic::generate())?;
+        store.retain("kept");
+        store.set("kept", String::from("forever"))?;
         store.clear();
         assert!(store.get::<String>("mine")?.is_none());
+        assert_eq!(store.get::<String>("kept")?.as_deref(), Some("forever"));
         Ok(())
     }
 
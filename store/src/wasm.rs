@@ -1,14 +1,29 @@
 use {
     gloo_storage::{LocalStorage, Storage},
     serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeSet,
+        sync::{Arc, RwLock},
+    },
 };
 
-#[derive(Clone)]
-pub struct KvStorage {}
+#[derive(Clone, Default)]
+pub struct KvStorage {
+    /// Keys that survive [`KvStorage::clear`]
+    retained: Arc<RwLock<BTreeSet<String>>>,
+}
 
 impl KvStorage {
     pub fn new() -> Self {
-        KvStorage {}
+        Self::default()
+    }
+
+    /// Keep `key` when the storage is cleared, e.g. for long lived identities
+    /// that must outlive the pairing state.
+    pub fn retain(&self, key: impl Into<String>) {
+        if let Ok(mut retained) = self.retained.write() {
+            retained.insert(key.into());
+        }
     }
 
     pub fn mem() -> Self {
@@ -38,7 +53,22 @@ impl KvStorage {
 
     #[tracing::instrument(level = "debug", skip(self))]
     pub fn clear(&self) {
+        let kept: Vec<(String, serde_json::Value)> = self
+            .retained
+            .read()
+            .map(|retained| {
+                retained
+                    .iter()
+                    .filter_map(|k| LocalStorage::get(k).ok().map(|v| (k.clone(), v)))
+                    .collect()
+            })
+            .unwrap_or_default();
         LocalStorage::clear();
+        for (k, v) in kept {
+            if let Err(e) = LocalStorage::set(&k, v) {
+                tracing::debug!("failed to restore retained key {k} {e}");
+            }
+        }
     }
 
     pub fn length(&self) -> u32 {